@@ -1,12 +1,12 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput, Data, Fields, Attribute, Meta, ItemFn};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, ItemFn, Meta};
 
 #[proc_macro_derive(GeminiSchema, attributes(gemini))]
 pub fn derive_gemini_schema(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
-    
+
     let description = get_description(&input.attrs);
     let description_token = match description {
         Some(d) => quote! { Some(#d.to_string()) },
@@ -19,7 +19,7 @@ pub fn derive_gemini_schema(input: TokenStream) -> TokenStream {
                 Fields::Named(fields) => fields.named,
                 _ => panic!("GeminiSchema only supports structs with named fields"),
             };
-            
+
             let prop_gen = fields.iter().map(|f| {
                 let f_name = f.ident.as_ref().unwrap().to_string();
                 let f_type = &f.ty;
@@ -36,16 +36,16 @@ pub fn derive_gemini_schema(input: TokenStream) -> TokenStream {
                     }
                 }
             });
-            
+
             let required_gen = fields.iter().map(|f| {
                 let f_name = f.ident.as_ref().unwrap().to_string();
                 quote! { #f_name.to_string() }
             });
 
             quote! {
-                let mut properties = std::collections::HashMap::new();
+                let mut properties = ::gemini_client_rs::types::IndexMap::new();
                 #(#prop_gen)*
-                
+
                 ::gemini_client_rs::types::Schema {
                     schema_type: ::gemini_client_rs::types::SchemaType::Object,
                     description: #description_token,
@@ -54,7 +54,7 @@ pub fn derive_gemini_schema(input: TokenStream) -> TokenStream {
                     ..Default::default()
                 }
             }
-        },
+        }
         _ => panic!("GeminiSchema only supports structs"),
     };
 
@@ -74,32 +74,89 @@ pub fn gemini_tool(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input_fn = parse_macro_input!(item as ItemFn);
     let fn_ident = &input_fn.sig.ident;
     let fn_name_str = fn_ident.to_string();
-    
+
     let struct_name = format!("{}Tool", snake_to_camel(&fn_name_str));
     let struct_ident = syn::Ident::new(&struct_name, fn_ident.span());
-    
+
     let description = get_description(&input_fn.attrs).unwrap_or_default();
-    
+
     let mut param_props = quote! {};
     let mut required_params = quote! {};
-    
+    let mut arg_parsers = quote! {};
+    let mut arg_idents = vec![];
+
     for arg in &input_fn.sig.inputs {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
-                let arg_name = pat_ident.ident.to_string();
-                let arg_type = &pat_type.ty;
-                param_props = quote! {
-                    #param_props
-                    properties.insert(#arg_name.to_string(), <#arg_type as ::gemini_client_rs::types::GeminiSchema>::schema());
-                };
-                required_params = quote! {
-                    #required_params
-                    required.push(#arg_name.to_string());
-                };
+        let pat_type = match arg {
+            syn::FnArg::Typed(pat_type) => pat_type,
+            syn::FnArg::Receiver(receiver) => {
+                return syn::Error::new_spanned(receiver, "gemini_tool does not support methods")
+                    .to_compile_error()
+                    .into();
             }
+        };
+        let syn::Pat::Ident(pat_ident) = &*pat_type.pat else {
+            return syn::Error::new_spanned(
+                &pat_type.pat,
+                "gemini_tool arguments must be plain identifiers, which name the parameters",
+            )
+            .to_compile_error()
+            .into();
+        };
+        let arg_ident = &pat_ident.ident;
+        let arg_name = arg_ident.to_string();
+        let arg_type = &pat_type.ty;
+        param_props = quote! {
+            #param_props
+            properties.insert(#arg_name.to_string(), <#arg_type as ::gemini_client_rs::types::GeminiSchema>::schema());
+        };
+        // The model may leave out optional arguments, which then parse as
+        // `None`.
+        if !is_option(arg_type) {
+            required_params = quote! {
+                #required_params
+                required.push(#arg_name.to_string());
+            };
         }
+        arg_parsers = quote! {
+            #arg_parsers
+            let #arg_ident: #arg_type = ::gemini_client_rs::__private::serde_json::from_value(
+                call.arguments.get(#arg_name).cloned().unwrap_or_default(),
+            )?;
+        };
+        arg_idents.push(arg_ident.clone());
     }
 
+    let call_doc = format!(
+        "Runs [`{fn_name_str}`] with the arguments of `call`, serializing its return value as the function response."
+    );
+    let call_impl = if input_fn.sig.asyncness.is_none() {
+        quote! {
+            impl #struct_ident {
+                #[doc = #call_doc]
+                pub fn call(
+                    call: &::gemini_client_rs::types::FunctionCall,
+                ) -> ::gemini_client_rs::types::FunctionResult {
+                    #arg_parsers
+                    let result = #fn_ident(#(#arg_idents),*);
+                    Ok(::gemini_client_rs::__private::serde_json::to_value(result)?)
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #struct_ident {
+                #[doc = #call_doc]
+                pub async fn call(
+                    call: &::gemini_client_rs::types::FunctionCall,
+                ) -> ::gemini_client_rs::types::FunctionResult {
+                    #arg_parsers
+                    let result = #fn_ident(#(#arg_idents),*).await;
+                    Ok(::gemini_client_rs::__private::serde_json::to_value(result)?)
+                }
+            }
+        }
+    };
+
     let expanded = quote! {
         #input_fn
 
@@ -107,10 +164,11 @@ pub fn gemini_tool(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
         impl ::gemini_client_rs::types::GeminiTool for #struct_ident {
             fn declaration() -> ::gemini_client_rs::types::FunctionDeclaration {
-                let mut properties = std::collections::HashMap::new();
+                let mut properties = ::gemini_client_rs::types::IndexMap::new();
                 let mut required = Vec::new();
                 #param_props
-                
+                #required_params
+
                 ::gemini_client_rs::types::FunctionDeclaration {
                     name: #fn_name_str.to_string(),
                     description: #description.to_string(),
@@ -124,6 +182,8 @@ pub fn gemini_tool(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             }
         }
+
+        #call_impl
     };
 
     TokenStream::from(expanded)
@@ -144,6 +204,24 @@ fn get_description(attrs: &[Attribute]) -> Option<String> {
     None
 }
 
+/// Whether `ty` is written as `Option<_>`, including through a path such as
+/// `std::option::Option<_>`.
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(path) => {
+            path.qself.is_none()
+                && path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Option")
+        }
+        syn::Type::Group(group) => is_option(&group.elem),
+        syn::Type::Paren(paren) => is_option(&paren.elem),
+        _ => false,
+    }
+}
+
 fn snake_to_camel(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let mut capitalize_next = true;
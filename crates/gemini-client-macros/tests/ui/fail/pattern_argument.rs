@@ -0,0 +1,9 @@
+use gemini_client_rs::gemini_tool;
+
+/// Adds two numbers.
+#[gemini_tool]
+fn add((a, b): (i64, i64)) -> i64 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use gemini_client_rs::types::{FunctionCall, GeminiTool, GenerateContentRequest};
+use gemini_client_rs::{gemini_tool, FunctionCallingEvent, GeminiClient, MockTransport};
+use serde_json::json;
+
+/// Looks up a user by id.
+#[gemini_tool]
+async fn find_user(id: i32) -> String {
+    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    format!("user {id}")
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let declaration = FindUserTool::declaration();
+    assert_eq!(declaration.name, "find_user");
+
+    let call = FunctionCall {
+        id: None,
+        name: "find_user".to_string(),
+        arguments: json!({ "id": 7 }),
+    };
+    let result = FindUserTool::call(&call)
+        .await
+        .expect("call should succeed");
+    assert_eq!(result, json!("user 7"));
+
+    // The generated async `call` can drive a function calling stream.
+    let transport = Arc::new(MockTransport::new());
+    transport.push_events(&[json!({
+        "candidates": [{ "content": { "role": "model", "parts": [
+            { "functionCall": { "id": "1", "name": "find_user", "args": { "id": 7 } } }
+        ] } }]
+    })]);
+    transport.push_events(&[json!({
+        "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Found them." }] } }]
+    })]);
+    let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+    let events: Vec<_> = client
+        .stream_generate_content_with_function_calling(
+            "gemini-2.5-flash",
+            GenerateContentRequest::default(),
+            5,
+            |call| async move { FindUserTool::call(&call).await },
+        )
+        .map(|event| event.expect("event should succeed"))
+        .collect()
+        .await;
+
+    assert!(matches!(
+        &events[1],
+        FunctionCallingEvent::ToolResult(response) if response.response.content == json!("user 7")
+    ));
+    assert!(matches!(&events[2], FunctionCallingEvent::TextDelta(text) if text == "Found them."));
+    assert_eq!(
+        transport.requests()[1].body["contents"][1]["parts"][0]["functionResponse"]["response"],
+        json!({ "content": "user 7" })
+    );
+}
@@ -0,0 +1,30 @@
+use gemini_client_rs::gemini_tool;
+use gemini_client_rs::types::{FunctionCall, GeminiTool};
+use serde_json::json;
+
+/// Gets the weather for a location.
+#[gemini_tool]
+fn get_weather(location: String, unit: Option<String>, days: std::option::Option<i32>) -> String {
+    format!("{location} {unit:?} {days:?}")
+}
+
+fn main() {
+    let declaration = GetWeatherTool::declaration();
+    assert_eq!(declaration.description, "Gets the weather for a location.");
+    let parameters = declaration.parameters.expect("parameters");
+    let properties: Vec<_> = parameters
+        .properties
+        .expect("properties")
+        .into_keys()
+        .collect();
+    assert_eq!(properties, ["location", "unit", "days"]);
+    assert_eq!(parameters.required, Some(vec!["location".to_string()]));
+
+    let call = FunctionCall {
+        id: None,
+        name: "get_weather".to_string(),
+        arguments: json!({ "location": "Paris" }),
+    };
+    let result = GetWeatherTool::call(&call).expect("call should succeed");
+    assert_eq!(result, json!("Paris None None"));
+}
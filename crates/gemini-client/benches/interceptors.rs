@@ -0,0 +1,95 @@
+//! Measures the cost of interceptors on `generateContent` calls with a large
+//! inline image, which an interceptor must not copy unless it modifies the
+//! request.
+//!
+//! Run with `cargo bench -p gemini_client_rs --bench interceptors`.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use gemini_client_rs::types::{Content, GenerateContentRequest, Part, Role};
+use gemini_client_rs::{GeminiClient, GeminiError, Interceptor, Transport};
+
+fn main() {
+    divan::main();
+}
+
+/// Answers every request with an empty response, without reading it.
+#[derive(Debug)]
+struct EmptyResponse;
+
+#[async_trait::async_trait]
+impl Transport for EmptyResponse {
+    async fn send(&self, _request: reqwest::Request) -> Result<reqwest::Response, GeminiError> {
+        let response = http::Response::builder()
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(r#"{"candidates": []}"#)
+            .expect("response should build");
+        Ok(response.into())
+    }
+}
+
+#[derive(Debug)]
+struct Observe;
+
+impl Interceptor for Observe {
+    fn before(&self, request: &mut Cow<'_, GenerateContentRequest>) {
+        divan::black_box(request.contents.len());
+    }
+}
+
+#[derive(Debug)]
+struct Modify;
+
+impl Interceptor for Modify {
+    fn before(&self, request: &mut Cow<'_, GenerateContentRequest>) {
+        request.to_mut().contents[0]
+            .parts
+            .push(Part::text("Be concise."));
+    }
+}
+
+fn image_request() -> GenerateContentRequest {
+    let image = "A".repeat(4 * 1024 * 1024);
+    GenerateContentRequest {
+        contents: vec![Content::multimodal(
+            Role::User,
+            vec![
+                Part::text("Describe this image."),
+                Part::inline_data("image/png", image),
+            ],
+        )],
+        ..Default::default()
+    }
+}
+
+fn run(bencher: divan::Bencher, interceptors: &[Arc<dyn Interceptor>]) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime should build");
+    let client = interceptors.iter().fold(
+        GeminiClient::new("key".to_string()).with_transport(Arc::new(EmptyResponse)),
+        |client, interceptor| client.with_interceptor(interceptor.clone()),
+    );
+    let request = image_request();
+    bencher.bench_local(|| {
+        runtime
+            .block_on(client.generate_content("gemini-2.5-flash", &request))
+            .expect("request should succeed")
+    });
+}
+
+#[divan::bench]
+fn no_interceptors(bencher: divan::Bencher) {
+    run(bencher, &[]);
+}
+
+#[divan::bench]
+fn observing_interceptors(bencher: divan::Bencher) {
+    run(bencher, &[Arc::new(Observe), Arc::new(Observe)]);
+}
+
+#[divan::bench]
+fn modifying_interceptors(bencher: divan::Bencher) {
+    run(bencher, &[Arc::new(Modify), Arc::new(Modify)]);
+}
@@ -0,0 +1,68 @@
+use std::io::{BufRead as _, Write as _};
+
+use dotenvy::dotenv;
+use gemini_client_rs::{
+    types::{Part, Role},
+    GeminiClient,
+};
+
+/// An interactive multi-turn chat. The [`gemini_client_rs::ChatSession`]
+/// keeps the conversation history, alternating user and model turns.
+///
+/// Commands: `/reset` clears the history, `/history` prints it, and
+/// `/quit` exits.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let mut chat = client
+        .chat("gemini-3-flash-preview")
+        .with_system_prompt("You are a friendly, concise assistant.");
+
+    print!("> ");
+    std::io::stdout().flush()?;
+    for line in std::io::stdin().lock().lines() {
+        let line = line?;
+        match line.trim() {
+            "" => {}
+            "/quit" => break,
+            "/reset" => {
+                chat.clear();
+                println!("History cleared.");
+            }
+            "/history" => {
+                for content in chat.history() {
+                    let role = match content.role {
+                        Some(Role::Model) => "model",
+                        _ => "user",
+                    };
+                    for part in &content.parts {
+                        if let Part::Text { text } = part {
+                            println!("[{role}] {text}");
+                        }
+                    }
+                }
+            }
+            message => match chat.send_message(message).await {
+                Ok(response) => match response.model_content() {
+                    Some(content) => {
+                        for part in &content.parts {
+                            if let Part::Text { text } = part {
+                                println!("{text}");
+                            }
+                        }
+                    }
+                    None => println!("No content data"),
+                },
+                Err(error) => println!("Error: {error}"),
+            },
+        }
+        print!("> ");
+        std::io::stdout().flush()?;
+    }
+
+    Ok(())
+}
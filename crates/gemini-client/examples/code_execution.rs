@@ -0,0 +1,35 @@
+use gemini_client_rs::{gemini_chat, GeminiClient};
+
+use dotenvy::dotenv;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    let req = gemini_chat!(user(
+        "What is the sum of the first 50 prime numbers? Generate and run code for the calculation."
+    ))
+    .enable_code_execution();
+
+    let response = client.generate_content(model_name, &req).await?;
+
+    for candidate in &response.candidates {
+        for code in candidate.executable_code() {
+            println!("Code:\n{}", code.code);
+        }
+
+        for result in candidate.code_execution_results() {
+            println!("Outcome: {:?}", result.outcome);
+            if let Some(output) = &result.output {
+                println!("Output:\n{}", output);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use gemini_client_rs::{
+    gemini_chat,
+    types::{CachedContent, Content, Part},
+    GeminiClient,
+};
+
+use dotenvy::dotenv;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    // Caches need a minimum number of tokens, so build a large instruction.
+    let handbook = "Employees may work remotely up to three days per week. \
+                    Expense reports are due by the fifth business day of each month. "
+        .repeat(400);
+
+    let cache = client
+        .caches()
+        .create(
+            &CachedContent {
+                model: format!("models/{model_name}"),
+                display_name: Some("employee-handbook".to_string()),
+                system_instruction: Some(Content {
+                    parts: vec![Part::text(format!(
+                        "Answer questions using this handbook:\n{handbook}"
+                    ))],
+                    role: None,
+                }),
+                ..Default::default()
+            }
+            .with_ttl(Duration::from_secs(300)),
+        )
+        .await?;
+    let cache_name = cache
+        .name
+        .clone()
+        .expect("created cache should have a name");
+
+    println!("Created cache: {cache_name}");
+    println!("Expires at: {:?}", cache.expire_time);
+
+    for question in [
+        "How many days per week can I work remotely?",
+        "When are expense reports due?",
+    ] {
+        let mut req = gemini_chat!(user(question));
+        req.cached_content = Some(cache_name.clone());
+
+        let response = client.generate_content(model_name, &req).await?;
+
+        for candidate in &response.candidates {
+            if let Some(content_data) = &candidate.content {
+                for part in &content_data.parts {
+                    if let Part::Text { text } = part {
+                        println!("Text: {}", text);
+                    }
+                }
+            }
+        }
+
+        if let Some(usage) = &response.usage_metadata {
+            println!(
+                "Cached tokens: {:?} of {:?} prompt tokens",
+                usage.cached_content_token_count, usage.prompt_token_count
+            );
+        }
+    }
+
+    // Extend the cache lifetime if it is still needed, then clean up.
+    let refreshed = client
+        .caches()
+        .update_ttl(&cache_name, Duration::from_secs(600))
+        .await?;
+    println!("Refreshed expiry: {:?}", refreshed.expire_time);
+
+    client.caches().delete(&cache_name).await?;
+
+    Ok(())
+}
@@ -26,11 +26,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Use gemini_chat! with structured output configuration
     let mut req = gemini_chat!(user("Generate a happy greeting message"));
 
-    req.generation_config = Some(GenerationConfig {
-        response_mime_type: Some("application/json".to_string()),
-        response_schema: Some(GreetingResponse::schema()),
-        ..Default::default()
-    });
+    req.generation_config =
+        Some(GenerationConfig::default().json_mode_with_schema(GreetingResponse::schema()));
 
     let response = client.generate_content(model_name, &req).await?;
 
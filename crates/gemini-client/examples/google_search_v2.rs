@@ -0,0 +1,56 @@
+use gemini_client_rs::{
+    gemini_chat,
+    types::{GroundingChunk, Part, Tool},
+    GeminiClient,
+};
+
+use dotenvy::dotenv;
+
+/// Grounds an answer with the built-in Google Search tool of gemini-2.x
+/// models and prints the sources the model relied on.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    let mut req = gemini_chat!(user("Who won the most recent Formula 1 race?"));
+    req.tools.push(Tool::google_search());
+
+    let response = client.generate_content(model_name, &req).await?;
+
+    for candidate in &response.candidates {
+        for part in candidate
+            .content()
+            .map(|c| c.parts.as_slice())
+            .unwrap_or_default()
+        {
+            if let Part::Text { text } = part {
+                println!("{text}");
+            }
+        }
+
+        let Some(metadata) = &candidate.grounding_metadata else {
+            println!("The answer was not grounded.");
+            continue;
+        };
+
+        if !metadata.web_search_queries.is_empty() {
+            println!(
+                "\nSearch queries: {}",
+                metadata.web_search_queries.join(", ")
+            );
+        }
+
+        println!("\nSources:");
+        for chunk in &metadata.grounding_chunks {
+            let GroundingChunk::Web(web) = chunk;
+            println!("- {} ({})", web.title, web.uri);
+        }
+    }
+
+    Ok(())
+}
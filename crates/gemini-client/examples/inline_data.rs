@@ -1,9 +1,4 @@
-use gemini_client_rs::{
-    gemini_chat,
-    types::Part,
-    GeminiClient,
-};
-
+use gemini_client_rs::{gemini_chat, types::Part, GeminiClient};
 
 use dotenvy::dotenv;
 
@@ -19,12 +14,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let poem_base64 = String::from("YSBtZW93IGhlcmUgYSBtZW93IHRoZXJlIGEgbWVvdyAuLi4=");
 
     // Use gemini_chat! combined with gemini_parts! for multi-part messages
-    let mut req = gemini_chat!(
-        user("finish the rest of this poem")
-    );
+    let mut req = gemini_chat!(user("finish the rest of this poem"));
 
     // Append the inline data part
-    req.contents[0].parts.push(Part::inline_data("text/plain", poem_base64));
+    req.contents[0]
+        .parts
+        .push(Part::inline_data("text/plain", poem_base64));
 
     let response = client.generate_content(model_name, &req).await?;
 
@@ -40,4 +35,3 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
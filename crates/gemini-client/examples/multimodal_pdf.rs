@@ -0,0 +1,50 @@
+use gemini_client_rs::{
+    gemini_chat,
+    types::{FilePollOptions, Part},
+    GeminiClient,
+};
+
+use dotenvy::dotenv;
+
+/// Summarizes a PDF through the Files API: upload it, wait for it to become
+/// `ACTIVE`, reference it in a `FileData` part and delete it afterwards.
+///
+/// Usage: `cargo run --example multimodal_pdf -- path/to/document.pdf`
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+    let path = std::env::args()
+        .nth(1)
+        .expect("usage: multimodal_pdf <path/to/document.pdf>");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    let file = client
+        .files()
+        .upload_file_and_wait(&path, FilePollOptions::default())
+        .await?;
+    println!("Uploaded {} as {}", path, file.name);
+
+    let mut req = gemini_chat!(user("Summarize this document in five bullet points."));
+    req.contents[0].parts.push(Part::from_file(&file));
+
+    // Delete the file even if generation fails, then report the outcome.
+    let response = client.generate_content(model_name, &req).await;
+    client.files().delete_file(&file.name).await?;
+    println!("Deleted {}", file.name);
+
+    for candidate in &response?.candidates {
+        if let Some(content) = candidate.content() {
+            for part in &content.parts {
+                if let Part::Text { text } = part {
+                    println!("{text}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
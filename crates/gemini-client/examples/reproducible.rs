@@ -0,0 +1,60 @@
+use gemini_client_rs::{
+    gemini_chat,
+    types::{GenerationConfig, Part},
+    GeminiClient,
+};
+
+use dotenvy::dotenv;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    let mut req = gemini_chat!(user("Invent a name for a coffee shop run by robots."));
+    req.generation_config = Some(GenerationConfig {
+        seed: Some(42),
+        temperature: Some(0.0),
+        ..Default::default()
+    });
+
+    let mut outputs = vec![];
+    for _ in 0..2 {
+        let response = client.generate_content(model_name, &req).await?;
+
+        let text = response
+            .candidates
+            .first()
+            .and_then(|candidate| candidate.content.as_ref())
+            .map(|content| {
+                content
+                    .parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+
+        println!("Text: {}", text);
+        outputs.push(text);
+    }
+
+    // Seeded sampling is best effort, and some models ignore the seed.
+    if outputs[0] != outputs[1] {
+        return Err(format!(
+            "{model_name} did not honor the seed: identical seeded requests returned different text"
+        )
+        .into());
+    }
+
+    println!("Both runs returned identical text.");
+
+    Ok(())
+}
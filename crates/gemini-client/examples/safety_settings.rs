@@ -0,0 +1,59 @@
+use gemini_client_rs::{
+    gemini_chat,
+    types::{HarmBlockThreshold, HarmCategory, Part, SafetySetting},
+    GeminiClient,
+};
+
+use dotenvy::dotenv;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+
+    let api_key = std::env::var("GEMINI_API_KEY").expect("GEMINI_API_KEY must be set");
+
+    let client = GeminiClient::new(api_key);
+    let model_name = "gemini-2.5-flash";
+
+    let mut req = gemini_chat!(user("Write a short, dramatic villain monologue."));
+
+    // BLOCK_NONE disables blocking but the model still reports the harm
+    // probability for each category, so the ratings can be read back below.
+    req.safety_settings = [
+        HarmCategory::Harassment,
+        HarmCategory::HateSpeech,
+        HarmCategory::SexuallyExplicit,
+        HarmCategory::DangerousContent,
+    ]
+    .into_iter()
+    .map(|category| SafetySetting::new(category, HarmBlockThreshold::BlockNone))
+    .collect();
+
+    // Civic integrity does not accept BLOCK_NONE on some models; OFF turns
+    // the filter off entirely, so no rating is reported for it.
+    req.safety_settings.push(SafetySetting::new(
+        HarmCategory::CivicIntegrity,
+        HarmBlockThreshold::Off,
+    ));
+
+    let response = client.generate_content(model_name, &req).await?;
+
+    for candidate in &response.candidates {
+        if let Some(content_data) = &candidate.content {
+            for part in &content_data.parts {
+                if let Part::Text { text } = part {
+                    println!("Text: {}", text);
+                }
+            }
+        }
+
+        for rating in candidate.safety_ratings.iter().flatten() {
+            println!(
+                "{:?}: {:?} (blocked: {})",
+                rating.category, rating.probability, rating.blocked
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,8 +1,4 @@
-use gemini_client_rs::{
-    gemini_chat, 
-    types::Part,
-    GeminiClient,
-};
+use gemini_client_rs::{gemini_chat, types::Part, GeminiClient};
 
 use dotenvy::dotenv;
 
@@ -35,4 +31,3 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
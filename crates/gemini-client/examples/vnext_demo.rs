@@ -1,5 +1,5 @@
-use gemini_client_rs::{GeminiClient, gemini_chat, gemini_parts, GeminiSchema, gemini_tool};
-use gemini_client_rs::types::{GeminiTool, GeminiSchema as _};
+use gemini_client_rs::types::{FunctionCall, GeminiSchema as _, GeminiTool};
+use gemini_client_rs::{gemini_chat, gemini_parts, gemini_tool, GeminiClient, GeminiSchema};
 
 #[derive(GeminiSchema)]
 #[allow(dead_code)]
@@ -22,13 +22,12 @@ fn get_weather(location: String) -> String {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _client = GeminiClient::default();
 
-    
     // 1. New Declarative Macro DX
     let _req = gemini_chat!(
         system("You are a helpful analyst."),
         user("Analyze the current economy.")
     );
-    
+
     // 2. Multimodal construction via gemini_parts!
     let _parts = gemini_parts![
         text("Analyze this:"),
@@ -44,5 +43,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let tool_decl = GetWeatherTool::declaration();
     println!("Tool: {:?}", tool_decl);
 
+    // 5. Tool execution via the generated handler
+    let call = FunctionCall {
+        id: None,
+        name: "get_weather".to_string(),
+        arguments: serde_json::json!({ "location": "Paris" }),
+    };
+    let result = GetWeatherTool::call(&call).map_err(|error| error.to_string())?;
+    println!("Result: {result}");
+
     Ok(())
 }
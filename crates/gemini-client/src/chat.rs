@@ -0,0 +1,275 @@
+use crate::types::{
+    Content, GenerateContentRequest, GenerateContentResponse, GenerationConfig, Part, Role,
+};
+use crate::{GeminiClient, GeminiError};
+
+/// A multi-turn conversation with a model, created with
+/// [`GeminiClient::chat`].
+///
+/// The session keeps the history of user and model turns and sends it with
+/// each message, along with its system instruction, tools and generation
+/// config.
+///
+/// ```rust,no_run
+/// # use gemini_client_rs::GeminiClient;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), gemini_client_rs::GeminiError> {
+/// let client = GeminiClient::default();
+/// let mut chat = client
+///     .chat("gemini-2.5-flash")
+///     .with_system_prompt("You are a friendly, concise assistant.");
+///
+/// chat.send_message("Hi, I'm Ada.").await?;
+/// let response = chat.send_message("What's my name?").await?;
+/// println!("{:?}", response.model_content());
+/// assert_eq!(chat.history().len(), 4);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChatSession {
+    client: GeminiClient,
+    model: String,
+    request: GenerateContentRequest,
+}
+
+impl ChatSession {
+    pub(crate) fn new(client: GeminiClient, model: &str) -> Self {
+        Self {
+            client,
+            model: model.to_string(),
+            request: GenerateContentRequest::default(),
+        }
+    }
+
+    /// Sets the system instruction to a single text part.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.request = self.request.with_system_prompt(prompt);
+        self
+    }
+
+    /// Sets the generation config used for every message, unless
+    /// overridden with [`ChatSession::send_message_with_config`].
+    pub fn with_generation_config(mut self, config: GenerationConfig) -> Self {
+        self.request.generation_config = Some(config);
+        self
+    }
+
+    /// Sends `request`'s system instruction, tools and other settings with
+    /// every message. Its `contents` become the initial history.
+    pub fn with_request(mut self, request: GenerateContentRequest) -> Self {
+        self.request = request;
+        self
+    }
+
+    /// The user and model turns so far, in order.
+    pub fn history(&self) -> &[Content] {
+        &self.request.contents
+    }
+
+    /// Forgets the history, keeping the session's settings.
+    pub fn clear(&mut self) {
+        self.request.contents.clear();
+    }
+
+    /// Sends `message` as the next user turn along with the history.
+    ///
+    /// The message and the model's reply are added to the history once the
+    /// reply arrives. If the call fails, is cancelled, or the response has
+    /// no content, e.g. because the prompt was blocked, the history is left
+    /// unchanged.
+    pub async fn send_message(
+        &mut self,
+        message: &str,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        self.send(message, None).await
+    }
+
+    /// Like [`ChatSession::send_message`], but uses `config` instead of the
+    /// session's generation config for this message only, e.g. to raise the
+    /// temperature for a brainstorming turn.
+    pub async fn send_message_with_config(
+        &mut self,
+        message: &str,
+        config: GenerationConfig,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        self.send(message, Some(config)).await
+    }
+
+    async fn send(
+        &mut self,
+        message: &str,
+        config: Option<GenerationConfig>,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        let turn = Turn::start(&mut self.request, message, config);
+        let response = self
+            .client
+            .generate_content(&self.model, turn.request)
+            .await?;
+        if let Some(content) = response.model_content() {
+            turn.answer(content);
+        }
+        Ok(response)
+    }
+}
+
+/// A user message added to a session's request for a single call, along
+/// with any generation config override. Dropping it before
+/// [`Turn::answer`] removes the message, and the override is always undone,
+/// so a failed or cancelled call leaves the session as it was.
+struct Turn<'a> {
+    request: &'a mut GenerateContentRequest,
+    default_config: Option<Option<GenerationConfig>>,
+    answered: bool,
+}
+
+impl<'a> Turn<'a> {
+    fn start(
+        request: &'a mut GenerateContentRequest,
+        message: &str,
+        config: Option<GenerationConfig>,
+    ) -> Self {
+        request
+            .contents
+            .push(Content::multimodal(Role::User, vec![Part::text(message)]));
+        let default_config = config.map(|config| request.generation_config.replace(config));
+        Self {
+            request,
+            default_config,
+            answered: false,
+        }
+    }
+
+    fn answer(mut self, content: Content) {
+        self.request.contents.push(content);
+        self.answered = true;
+    }
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        if let Some(config) = self.default_config.take() {
+            self.request.generation_config = config;
+        }
+        if !self.answered {
+            self.request.contents.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::transport::MockTransport;
+    use crate::types::{GenerationConfig, Role};
+    use crate::{GeminiClient, GeminiError};
+
+    fn reply(text: &str) -> serde_json::Value {
+        json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": text }] } }]
+        })
+    }
+
+    fn chat_client() -> (GeminiClient, Arc<MockTransport>) {
+        let transport = Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+        (client, transport)
+    }
+
+    #[tokio::test]
+    async fn messages_are_sent_with_the_history() {
+        let (client, transport) = chat_client();
+        transport.push_json(&reply("Hi Ada!"));
+        transport.push_json(&reply("Your name is Ada."));
+
+        let mut chat = client
+            .chat("gemini-2.5-flash")
+            .with_system_prompt("Be concise.");
+        chat.send_message("Hi, I'm Ada.")
+            .await
+            .expect("first message should succeed");
+        chat.send_message("What's my name?")
+            .await
+            .expect("second message should succeed");
+
+        let roles: Vec<_> = chat.history().iter().map(|content| content.role).collect();
+        assert_eq!(
+            roles,
+            [
+                Some(Role::User),
+                Some(Role::Model),
+                Some(Role::User),
+                Some(Role::Model)
+            ]
+        );
+        let requests = transport.requests();
+        assert_eq!(
+            requests[1].body["systemInstruction"]["parts"][0]["text"],
+            "Be concise."
+        );
+        assert_eq!(
+            requests[1].body["contents"],
+            json!([
+                { "role": "user", "parts": [{ "text": "Hi, I'm Ada." }] },
+                { "role": "model", "parts": [{ "text": "Hi Ada!" }] },
+                { "role": "user", "parts": [{ "text": "What's my name?" }] }
+            ])
+        );
+
+        chat.clear();
+        assert!(chat.history().is_empty());
+    }
+
+    #[tokio::test]
+    async fn config_overrides_apply_to_a_single_message() {
+        let (client, transport) = chat_client();
+        for _ in 0..2 {
+            transport.push_json(&reply("Ok."));
+        }
+
+        let mut chat = client
+            .chat("gemini-2.5-flash")
+            .with_generation_config(GenerationConfig {
+                temperature: Some(0.25),
+                ..Default::default()
+            });
+        chat.send_message_with_config(
+            "Brainstorm names.",
+            GenerationConfig {
+                temperature: Some(1.5),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("message should succeed");
+        chat.send_message("Pick one.")
+            .await
+            .expect("message should succeed");
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].body["generationConfig"]["temperature"], 1.5);
+        assert_eq!(requests[1].body["generationConfig"]["temperature"], 0.25);
+    }
+
+    #[tokio::test]
+    async fn failed_messages_leave_the_history_unchanged() {
+        let (client, transport) = chat_client();
+        transport.push_json(&reply("Hello!"));
+        transport.push_response(500, r#"{"error": {"code": 500, "message": "internal"}}"#);
+
+        let mut chat = client.chat("gemini-2.5-flash");
+        chat.send_message("Hi")
+            .await
+            .expect("message should succeed");
+        let result = chat
+            .send_message_with_config("Again", GenerationConfig::default())
+            .await;
+
+        assert!(matches!(result, Err(GeminiError::Api(_))), "{result:?}");
+        assert_eq!(chat.history().len(), 2);
+        assert!(chat.request.generation_config.is_none());
+    }
+}
@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+
+use crate::types::{GenerateContentRequest, GenerateContentResponse};
+
+/// Middleware for `generateContent` calls, registered with
+/// [`crate::GeminiClient::with_interceptor`].
+///
+/// Interceptors run in registration order, both before a request is
+/// validated and sent, and after each response, or each streamed chunk, is
+/// parsed.
+///
+/// Requests are passed as a [`Cow`] borrowing the caller's request, so that
+/// interceptors which only inspect it do not copy it. Calling
+/// [`Cow::to_mut`] copies the request once, for all interceptors.
+///
+/// ```rust
+/// # use std::borrow::Cow;
+/// # use std::sync::Arc;
+/// # use gemini_client_rs::{GeminiClient, Interceptor};
+/// # use gemini_client_rs::types::{GenerateContentRequest, GenerateContentResponse};
+/// #[derive(Debug)]
+/// struct DefaultSystemPrompt;
+///
+/// impl Interceptor for DefaultSystemPrompt {
+///     fn before(&self, request: &mut Cow<'_, GenerateContentRequest>) {
+///         if request.system_instruction.is_none() {
+///             let request = request.to_mut();
+///             *request = std::mem::take(request).with_system_prompt("Be concise.");
+///         }
+///     }
+/// }
+///
+/// let client = GeminiClient::default().with_interceptor(Arc::new(DefaultSystemPrompt));
+/// ```
+pub trait Interceptor: std::fmt::Debug + Send + Sync {
+    /// Inspects or modifies a request before it is sent.
+    fn before(&self, _request: &mut Cow<'_, GenerateContentRequest>) {}
+
+    /// Modifies a response, or a streamed chunk, before it is returned.
+    fn after(&self, _response: &mut GenerateContentResponse) {}
+}
@@ -1,27 +1,67 @@
+use eventsource_stream::Eventsource as _;
 use futures_util::{Stream, StreamExt as _};
 use reqwest::Client;
-use reqwest_eventsource::{Event, RequestBuilderExt as _};
 use serde_json::Value;
 use std::pin::Pin;
 use types::{
-    BatchEmbedContentsRequest, BatchEmbedContentsResponse, EmbedContentRequest,
-    EmbedContentResponse, GenerateContentRequest, GenerateContentResponse,
+    BatchEmbedContentsRequest, BatchEmbedContentsResponse, CountTokensResponse,
+    EmbedContentRequest, EmbedContentResponse, GenerateContentRequest, GenerateContentResponse,
 };
 
+mod chat;
+mod interceptor;
+mod partial_json;
 mod telemetry;
+mod transport;
 pub mod types;
 
+pub use chat::ChatSession;
+pub use interceptor::Interceptor;
+pub use partial_json::StreamingJsonAccumulator;
+#[cfg(feature = "testing")]
+pub use transport::{MockRequest, MockTransport};
+pub use transport::{ReqwestTransport, Transport};
+
+/// A stream of response chunks from [`GeminiClient::stream_generate_content`].
+///
+/// The stream owns the underlying HTTP response and spawns no background
+/// tasks. Dropping it before the end aborts the in-flight response body and
+/// closes the connection instead of returning it to the pool half-read.
 pub type GeminiResponseStream =
     Pin<Box<dyn Stream<Item = Result<GenerateContentResponse, GeminiError>> + Send>>;
 
+pub type GeminiModelStream = Pin<Box<dyn Stream<Item = Result<types::Model, GeminiError>> + Send>>;
+
+/// A stream of events from
+/// [`GeminiClient::stream_generate_content_with_function_calling`].
+pub type GeminiFunctionCallingStream =
+    Pin<Box<dyn Stream<Item = Result<FunctionCallingEvent, GeminiError>> + Send>>;
+
+/// A stream of embeddings from [`GeminiClient::embed_corpus`].
+pub type GeminiEmbeddingStream =
+    Pin<Box<dyn Stream<Item = Result<types::ContentEmbedding, GeminiError>> + Send>>;
+
+/// Progress of a function calling conversation.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FunctionCallingEvent {
+    /// The model requested a function call, which is about to be executed.
+    ToolCall(types::FunctionCall),
+    /// A function call finished and its result will be sent to the model.
+    ToolResult(types::FunctionResponse),
+    /// A chunk of streamed model text.
+    TextDelta(String),
+}
+
 pub use gemini_client_macros::{gemini_tool, GeminiSchema};
 
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum GeminiError {
     #[error("HTTP Error: {0}")]
-    Http(#[from] reqwest::Error),
+    Http(#[source] reqwest::Error),
     #[error("Streaming Event Error: {0}")]
-    EventSource(#[from] reqwest_eventsource::Error),
+    EventSource(#[source] Box<reqwest_eventsource::Error>),
     #[error("API Error: {0}")]
     Api(Value),
     #[error("JSON Error: {error} (payload: {data})")]
@@ -30,36 +70,307 @@ pub enum GeminiError {
         #[source]
         error: serde_json::Error,
     },
+    #[error("Prompt Blocked: {0:?}")]
+    Blocked(types::PromptFeedback),
+    #[error("Embedding Dimension Mismatch: expected {expected}, got {actual}")]
+    DimensionMismatch { expected: usize, actual: usize },
+    #[error("Invalid Request: {0}")]
+    InvalidRequest(String),
+    #[error("Unknown Model: {0}")]
+    UnknownModel(String),
+    #[error("Request timed out")]
+    Timeout,
+    #[error("IO Error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Function Execution Error in {name}: {message}")]
+    FunctionExecution { name: String, message: String },
+    #[error("File Processing Failed: {0}")]
+    FileProcessingFailed(String),
+    #[error("Invalid API Key: {0}")]
+    InvalidApiKey(Value),
+    #[error("Rate Limited: {message}")]
+    RateLimited {
+        message: String,
+        /// How long the API asked to wait before retrying, if reported.
+        retry_after: Option<std::time::Duration>,
+        /// The exceeded quotas, e.g. to tell a per-day limit from a
+        /// per-minute one.
+        violations: Vec<types::QuotaViolation>,
+    },
+    #[error("Response Too Large: the body exceeds {limit} bytes")]
+    ResponseTooLarge { limit: usize },
+    #[error("Too Many Turns: the model still called functions after {max_turns} turns")]
+    TooManyTurns { max_turns: usize },
+    #[error("Embedding Count Mismatch: expected {expected}, got {actual}")]
+    EmbeddingCountMismatch { expected: usize, actual: usize },
+    /// Yielded by [`GeminiClient::embed_corpus`] for each text of a batch
+    /// that failed, all sharing the batch's error.
+    #[error("Embedding Batch Failed for text {index}: {source}")]
+    EmbeddingBatchFailed {
+        /// The position of the text in the input stream.
+        index: usize,
+        source: std::sync::Arc<GeminiError>,
+    },
 }
 
 impl GeminiError {
+    /// Whether the API rejected the API key, either with a 401 or 403
+    /// status or with an `API_KEY_INVALID` error, e.g. to prompt the user to
+    /// check their key.
+    pub fn is_auth_error(&self) -> bool {
+        match self {
+            Self::InvalidApiKey(_) => true,
+            Self::Api(value) => {
+                matches!(value["status"].as_u64(), Some(401 | 403))
+                    || value["message"].to_string().contains("API_KEY_INVALID")
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads the response body and deserializes it, keeping the raw body on
+    /// [`GeminiError::Json`] if it does not match the expected shape.
+    async fn parse_response<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+        limit: Option<usize>,
+    ) -> Result<T, Self> {
+        let data = Self::read_body(response, limit).await?;
+        Self::parse_body(data)
+    }
+
+    /// Reads the response body, failing with
+    /// [`GeminiError::ResponseTooLarge`] as soon as it exceeds `limit` bytes
+    /// instead of buffering the rest.
+    pub(crate) async fn read_body(
+        mut response: reqwest::Response,
+        limit: Option<usize>,
+    ) -> Result<String, Self> {
+        let Some(limit) = limit else {
+            return response.text().await.map_err(Self::from_reqwest);
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > limit as u64)
+        {
+            return Err(Self::ResponseTooLarge { limit });
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(Self::from_reqwest)? {
+            if body.len() + chunk.len() > limit {
+                return Err(Self::ResponseTooLarge { limit });
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    fn parse_body<T: serde::de::DeserializeOwned>(data: String) -> Result<T, Self> {
+        serde_json::from_str(&data).map_err(|error| Self::Json { data, error })
+    }
+
+    /// Drops the URL from the error, since it carries the API key in its
+    /// `key` query parameter.
+    fn from_reqwest(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Http(error.without_url())
+        }
+    }
+
     async fn from_response(
         response: reqwest::Response,
         context: Option<serde_json::Value>,
+        limit: Option<usize>,
     ) -> Self {
-        let status = response.status();
-        let text = match response.text().await {
-            Ok(text) => text,
-            Err(error) => return Self::Http(error),
+        let status = response.status().as_u16();
+        match Self::read_body(response, limit).await {
+            Ok(text) => Self::from_status(status, text, context),
+            Err(error) => error,
+        }
+    }
+
+    /// Builds a [`GeminiError::RateLimited`] from the `QuotaFailure` and
+    /// `RetryInfo` details of a 429 response body.
+    fn rate_limited(body: &Value) -> Self {
+        let details = body["error"]["details"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let detail = |kind: &'static str| {
+            details.iter().filter(move |detail| {
+                detail["@type"]
+                    .as_str()
+                    .is_some_and(|type_url| type_url.ends_with(kind))
+            })
         };
+
+        let violations = detail("google.rpc.QuotaFailure")
+            .filter_map(|detail| detail["violations"].as_array())
+            .flatten()
+            .filter_map(|violation| serde_json::from_value(violation.clone()).ok())
+            .collect();
+        // `retryDelay` is a protobuf duration, e.g. `"39s"` or `"1.5s"`.
+        let retry_after = detail("google.rpc.RetryInfo")
+            .filter_map(|detail| detail["retryDelay"].as_str()?.strip_suffix('s'))
+            .find_map(|seconds| std::time::Duration::try_from_secs_f64(seconds.parse().ok()?).ok());
+        let message = match body["error"]["message"].as_str() {
+            Some(message) => message.to_string(),
+            None => body.to_string(),
+        };
+
+        Self::RateLimited {
+            message,
+            retry_after,
+            violations,
+        }
+    }
+
+    fn from_status(status: u16, text: String, context: Option<serde_json::Value>) -> Self {
         let message = match serde_json::from_str::<Value>(&text) {
             Ok(error) => error,
             Err(_) => serde_json::Value::String(text),
         };
 
+        if status == 429 {
+            return Self::rate_limited(&message);
+        }
+
         Self::Api(serde_json::json!({
-            "status": status.as_u16(),
+            "status": status,
             "message": message,
             "context": context.unwrap_or_default(),
         }))
     }
 }
 
-#[derive(Debug, Clone)]
+impl From<reqwest_eventsource::Error> for GeminiError {
+    fn from(error: reqwest_eventsource::Error) -> Self {
+        Self::EventSource(Box::new(error))
+    }
+}
+
+impl From<reqwest::Error> for GeminiError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::from_reqwest(error)
+    }
+}
+
+/// The Gemini API version to target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ApiVersion {
+    /// The stable API surface.
+    V1,
+    /// The beta API surface, which includes preview features.
+    #[default]
+    V1Beta,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V1Beta => "v1beta",
+        }
+    }
+}
+
+/// The non-secret settings of a [`GeminiClient`], as returned by
+/// [`GeminiClient::config`]. Credentials are deliberately left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ClientConfig {
+    pub base_url: String,
+    pub api_version: ApiVersion,
+    /// The full URL override set with [`GeminiClient::with_api_url`].
+    pub api_url: Option<String>,
+    pub stream_retries: usize,
+    /// The value of the `x-goog-api-client` header.
+    pub api_client: String,
+    pub max_response_bytes: Option<usize>,
+    /// The per-request timeout set with [`GeminiClient::with_timeout`].
+    pub timeout: Option<std::time::Duration>,
+}
+
+/// Adds the client's credentials to a request: the API key as the `key`
+/// query parameter, or the bearer token set with
+/// [`GeminiClient::with_bearer_token`]. Also identifies the client with the
+/// `x-goog-api-client` header.
+trait Authorize {
+    fn authorize(self, client: &GeminiClient) -> Self;
+}
+
+const API_CLIENT_HEADER: &str = "x-goog-api-client";
+
+impl Authorize for reqwest::RequestBuilder {
+    fn authorize(self, client: &GeminiClient) -> Self {
+        let request = self.header(API_CLIENT_HEADER, &client.api_client);
+        match client.bearer_token() {
+            Some(token) => request.bearer_auth(token),
+            None => request.query(&[("key", &client.api_key)]),
+        }
+    }
+}
+
+/// Whether `response` is a server-sent events stream, ignoring parameters
+/// such as `charset`.
+fn is_event_stream(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"))
+}
+
+/// Returns the resource name for a model, e.g. `models/gemini-2.5-flash`.
+///
+/// Bare names are treated as base models, while names that already carry a
+/// collection, such as `models/...` or `tunedModels/...`, are kept as-is.
+fn model_resource(model: &str) -> String {
+    if model.contains('/') {
+        model.to_string()
+    } else {
+        format!("models/{model}")
+    }
+}
+
+#[derive(Clone)]
 pub struct GeminiClient {
     api_key: String,
     http_client: Client,
-    api_url: String,
+    base_url: String,
+    api_version: ApiVersion,
+    api_url: Option<String>,
+    transport: Option<std::sync::Arc<dyn Transport>>,
+    interceptors: Vec<std::sync::Arc<dyn Interceptor>>,
+    bearer_token: std::sync::Arc<std::sync::RwLock<Option<String>>>,
+    stream_retries: usize,
+    api_client: String,
+    max_response_bytes: Option<usize>,
+    timeout: Option<std::time::Duration>,
+}
+
+// Written by hand so credentials never end up in logs or panic messages.
+impl std::fmt::Debug for GeminiClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeminiClient")
+            .field("api_key", &"***")
+            .field("http_client", &self.http_client)
+            .field("base_url", &self.base_url)
+            .field("api_version", &self.api_version)
+            .field("api_url", &self.api_url)
+            .field("transport", &self.transport)
+            .field("interceptors", &self.interceptors)
+            .field("bearer_token", &self.bearer_token().map(|_| "***"))
+            .field("stream_retries", &self.stream_retries)
+            .field("api_client", &self.api_client)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
 }
 
 impl Default for GeminiClient {
@@ -67,12 +378,154 @@ impl Default for GeminiClient {
         Self {
             api_key: std::env::var("GEMINI_API_KEY").unwrap_or_default(),
             http_client: Client::new(),
-            api_url: "https://generativelanguage.googleapis.com/v1beta".to_string(),
+            base_url: "https://generativelanguage.googleapis.com".to_string(),
+            api_version: ApiVersion::default(),
+            api_url: None,
+            transport: None,
+            interceptors: vec![],
+            bearer_token: Default::default(),
+            stream_retries: 0,
+            api_client: GeminiClient::DEFAULT_API_CLIENT.to_string(),
+            max_response_bytes: None,
+            timeout: None,
         }
     }
 }
 
+/// Builder for a [`GeminiClient`] that owns the construction of its
+/// [`reqwest::Client`], exposing HTTP settings such as proxies.
+///
+/// ```rust
+/// # use gemini_client_rs::GeminiClient;
+/// # fn main() -> Result<(), gemini_client_rs::GeminiError> {
+/// let proxy = reqwest::Proxy::https("http://proxy.internal:3128")?
+///     .no_proxy(reqwest::NoProxy::from_string("localhost,.internal"));
+/// let client = GeminiClient::builder()
+///     .api_key("my-api-key")
+///     .proxy(proxy)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GeminiClientBuilder {
+    client: GeminiClient,
+    http_client_builder: reqwest::ClientBuilder,
+}
+
+impl Default for GeminiClientBuilder {
+    fn default() -> Self {
+        Self {
+            client: GeminiClient::default(),
+            http_client_builder: Client::builder(),
+        }
+    }
+}
+
+impl GeminiClientBuilder {
+    /// Set the API key. Defaults to the `GEMINI_API_KEY` environment
+    /// variable.
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.client.api_key = api_key.into();
+        self
+    }
+
+    /// See [`GeminiClient::with_base_url`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.client.base_url = base_url.into();
+        self
+    }
+
+    /// See [`GeminiClient::with_api_version`].
+    pub fn api_version(mut self, api_version: ApiVersion) -> Self {
+        self.client.api_version = api_version;
+        self
+    }
+
+    /// See [`GeminiClient::with_api_url`].
+    pub fn api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.client.api_url = Some(api_url.into());
+        self
+    }
+
+    /// See [`GeminiClient::with_api_client`].
+    pub fn api_client(mut self, api_client: impl Into<String>) -> Self {
+        self.client.api_client = api_client.into();
+        self
+    }
+
+    /// See [`GeminiClient::with_max_response_bytes`].
+    pub fn max_response_bytes(mut self, limit: usize) -> Self {
+        self.client.max_response_bytes = Some(limit);
+        self
+    }
+
+    /// See [`GeminiClient::with_timeout`].
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.client.timeout = Some(timeout);
+        self
+    }
+
+    /// Route requests through `proxy`.
+    ///
+    /// By default the `HTTP_PROXY`/`HTTPS_PROXY`/`NO_PROXY` environment
+    /// variables are respected; adding a proxy here replaces them. Use
+    /// [`reqwest::Proxy::no_proxy`] to configure exceptions.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.http_client_builder = self.http_client_builder.proxy(proxy);
+        self
+    }
+
+    /// Keep at most `max` idle connections per host in the pool.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http_client_builder = self.http_client_builder.pool_max_idle_per_host(max);
+        self
+    }
+
+    /// Close pooled connections after they have been idle for `timeout`,
+    /// or never with `None`. Defaults to 90 seconds.
+    pub fn pool_idle_timeout(mut self, timeout: impl Into<Option<std::time::Duration>>) -> Self {
+        self.http_client_builder = self.http_client_builder.pool_idle_timeout(timeout);
+        self
+    }
+
+    /// Use HTTP/2 without negotiating it first, so that concurrent requests
+    /// share a connection.
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http_client_builder = self.http_client_builder.http2_prior_knowledge();
+        self
+    }
+
+    /// Ignore all proxies, including those from the environment.
+    pub fn no_proxy(mut self) -> Self {
+        self.http_client_builder = self.http_client_builder.no_proxy();
+        self
+    }
+
+    /// Build the client.
+    pub fn build(self) -> Result<GeminiClient, GeminiError> {
+        Ok(GeminiClient {
+            http_client: self.http_client_builder.build()?,
+            ..self.client
+        })
+    }
+}
+
 impl GeminiClient {
+    /// The maximum number of requests in a single `batchEmbedContents` call.
+    pub const MAX_BATCH_EMBED_SIZE: usize = 100;
+
+    /// The default `x-goog-api-client` header value, identifying this crate
+    /// and its version.
+    pub const DEFAULT_API_CLIENT: &'static str =
+        concat!("gemini-client-rs/", env!("CARGO_PKG_VERSION"));
+
+    /// Create a builder to configure the client and its underlying HTTP
+    /// client.
+    pub fn builder() -> GeminiClientBuilder {
+        GeminiClientBuilder::default()
+    }
+
     /// Create a new Gemini client.
     ///
     /// If you have the [`GEMINI_API_KEY`] environment variable set, you can use
@@ -93,23 +546,337 @@ impl GeminiClient {
         self
     }
 
-    /// Set the API URL for the Gemini client.
+    /// Send every request through `transport` instead of the
+    /// [`reqwest::Client`], e.g. to return canned responses in tests.
+    pub fn with_transport(mut self, transport: std::sync::Arc<dyn Transport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Authenticate with an OAuth2 access token, sent as an
+    /// `Authorization: Bearer` header, instead of the API key.
+    ///
+    /// Combined with [`GeminiClient::with_api_url`], this allows targeting
+    /// endpoints such as Vertex AI. Tokens are short-lived; refresh them with
+    /// [`GeminiClient::set_bearer_token`].
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = std::sync::Arc::new(std::sync::RwLock::new(Some(token.into())));
+        self
+    }
+
+    /// Replaces the bearer token of this client and every clone of it, e.g.
+    /// from a background task that refreshes the token before it expires.
+    ///
+    /// Requests already sent keep the token they were sent with.
+    pub fn set_bearer_token(&self, token: impl Into<String>) {
+        *self
+            .bearer_token
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(token.into());
+    }
+
+    fn bearer_token(&self) -> Option<String> {
+        self.bearer_token
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Retry [`GeminiClient::stream_generate_content`] up to `retries` times
+    /// when the connection fails before the first chunk arrives.
+    ///
+    /// The API has no way to resume a stream, so each retry resends the
+    /// whole request. Once a chunk has been yielded, a failure is returned
+    /// as an error instead, since the caller may already have used the
+    /// partial response. API errors, such as a 429, are never retried.
+    /// Defaults to 0.
+    pub fn with_stream_retries(mut self, retries: usize) -> Self {
+        self.stream_retries = retries;
+        self
+    }
+
+    /// Set the `x-goog-api-client` header sent with every request, e.g. to
+    /// identify an application built on this crate. Defaults to
+    /// [`GeminiClient::DEFAULT_API_CLIENT`].
+    pub fn with_api_client(mut self, api_client: impl Into<String>) -> Self {
+        self.api_client = api_client.into();
+        self
+    }
+
+    /// Adds an [`Interceptor`] to run on every `generateContent` call, after
+    /// any already registered.
+    pub fn with_interceptor(mut self, interceptor: std::sync::Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    /// Set the API URL for the Gemini client, including the version segment.
     ///
-    /// This is useful for testing purposes.
+    /// This takes precedence over [`GeminiClient::with_base_url`] and
+    /// [`GeminiClient::with_api_version`], and is useful for testing purposes.
+    /// File uploads use the same host, with `/upload` prepended to the path
+    /// as on the public API, e.g. `http://localhost:8080/upload/v1beta/files`
+    /// for `http://localhost:8080/v1beta`.
     pub fn with_api_url(mut self, api_url: String) -> Self {
-        self.api_url = api_url;
+        self.api_url = Some(api_url);
         self
     }
 
-    /// List all available models.
-    pub async fn list_models(&self) -> Result<Vec<types::Model>, GeminiError> {
-        #[derive(serde::Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Response {
-            models: Vec<types::Model>,
-            next_page_token: Option<String>,
+    /// Fail with [`GeminiError::ResponseTooLarge`] instead of buffering a
+    /// response body larger than `limit` bytes, e.g. to guard against a
+    /// misbehaving proxy.
+    ///
+    /// Applies to every response, including those returned by a custom
+    /// [`Transport`]. For [`GeminiClient::stream_generate_content`], the
+    /// limit caps the total size of the stream, and the error is yielded in
+    /// place of the next chunk. Unlimited by default.
+    pub fn with_max_response_bytes(mut self, limit: usize) -> Self {
+        self.max_response_bytes = Some(limit);
+        self
+    }
+
+    /// Fail with [`GeminiError::Timeout`] when a request takes longer than
+    /// `timeout`, from sending it until its body has been read.
+    ///
+    /// For [`GeminiClient::stream_generate_content`], this caps the whole
+    /// stream. Applies to every request, including those sent through a
+    /// client set with [`GeminiClient::with_client`], unless a custom
+    /// [`Transport`] ignores it. No timeout by default.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the base URL for the Gemini client, without the version segment.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Set the API version used to build request URLs. Defaults to
+    /// [`ApiVersion::V1Beta`].
+    pub fn with_api_version(mut self, api_version: ApiVersion) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// The client's non-secret settings, e.g. to assert on how a client was
+    /// configured in tests.
+    pub fn config(&self) -> ClientConfig {
+        ClientConfig {
+            base_url: self.base_url.clone(),
+            api_version: self.api_version,
+            api_url: self.api_url.clone(),
+            stream_retries: self.stream_retries,
+            api_client: self.api_client.clone(),
+            max_response_bytes: self.max_response_bytes,
+            timeout: self.timeout,
+        }
+    }
+
+    /// Reads and deserializes a response body, within the
+    /// [`GeminiClient::with_max_response_bytes`] limit.
+    async fn parse_response<T: serde::de::DeserializeOwned>(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<T, GeminiError> {
+        GeminiError::parse_response(response, self.max_response_bytes).await
+    }
+
+    /// Turns an unsuccessful response into an error, reading the body
+    /// within the [`GeminiClient::with_max_response_bytes`] limit.
+    async fn error_from_response(
+        &self,
+        response: reqwest::Response,
+        context: Option<Value>,
+    ) -> GeminiError {
+        GeminiError::from_response(response, context, self.max_response_bytes).await
+    }
+
+    /// Runs the interceptors on `request`, which is only copied if one of
+    /// them modifies it.
+    fn intercept_request<'r>(
+        &self,
+        request: &'r GenerateContentRequest,
+    ) -> std::borrow::Cow<'r, GenerateContentRequest> {
+        let mut request = std::borrow::Cow::Borrowed(request);
+        for interceptor in &self.interceptors {
+            interceptor.before(&mut request);
         }
+        request
+    }
+
+    /// Starts a request to `url` with the client's credentials added by
+    /// [`Authorize`]. Send it with [`GeminiClient::execute`].
+    fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        self.http_client.request(method, url).authorize(self)
+    }
+
+    /// Sends `request` through the configured [`Transport`], with the
+    /// [`GeminiClient::with_timeout`] timeout.
+    async fn execute(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, GeminiError> {
+        let mut request = request.build()?;
+        if let Some(timeout) = self.timeout {
+            *request.timeout_mut() = Some(timeout);
+        }
+        match &self.transport {
+            Some(transport) => transport.send(request).await,
+            None => {
+                ReqwestTransport::new(self.http_client.clone())
+                    .send(request)
+                    .await
+            }
+        }
+    }
+
+    /// `POST`s `body` to `url`, returning the status code and the body read
+    /// within the [`GeminiClient::with_max_response_bytes`] limit.
+    ///
+    /// `headers` replace the defaults, e.g. to send a different
+    /// `x-goog-api-client` with a single request.
+    async fn send_json<B: serde::Serialize + ?Sized>(
+        &self,
+        url: &str,
+        body: &B,
+        headers: &reqwest::header::HeaderMap,
+    ) -> Result<(u16, String), GeminiError> {
+        let body = serde_json::to_vec(body).map_err(|error| GeminiError::Json {
+            data: String::new(),
+            error,
+        })?;
+        let request = self
+            .request(reqwest::Method::POST, url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .headers(headers.clone())
+            .body(body);
+        let response = self.execute(request).await?;
+        let status = response.status().as_u16();
+        let body = GeminiError::read_body(response, self.max_response_bytes).await?;
+        Ok((status, body))
+    }
+
+    /// Sends a request made by a sub-client, recorded on a span tagged with
+    /// `operation`, e.g. `corpora.create`, and turns an unsuccessful status
+    /// into an error.
+    async fn send_checked(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, GeminiError> {
+        let span = crate::telemetry::telemetry_span_guard!(
+            info,
+            "gemini_client_rs.request",
+            operation,
+            status_code = tracing::field::Empty
+        );
+        let _ = operation;
+
+        span.instrument(async {
+            let response = match self.execute(request).await {
+                Ok(response) => response,
+                Err(error) => {
+                    crate::telemetry::telemetry_error!(
+                        error_kind = crate::telemetry::gemini_error_kind(&error),
+                        "request failed"
+                    );
+                    return Err(error);
+                }
+            };
+            span.record_status(response.status().as_u16());
+            if !response.status().is_success() {
+                let error = self.error_from_response(response, None).await;
+                crate::telemetry::telemetry_error!(
+                    error_kind = crate::telemetry::gemini_error_kind(&error),
+                    "API failure"
+                );
+                return Err(error);
+            }
+
+            crate::telemetry::telemetry_debug!("request completed");
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Like [`GeminiClient::send_checked`], and deserializes the response.
+    async fn send_parsed<T: serde::de::DeserializeOwned>(
+        &self,
+        operation: &'static str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, GeminiError> {
+        let response = self.send_checked(operation, request).await?;
+        self.parse_response(response).await.inspect_err(|_error| {
+            crate::telemetry::telemetry_error!(
+                operation,
+                error_kind = crate::telemetry::gemini_error_kind(_error),
+                "response parsing failed"
+            );
+        })
+    }
 
+    /// Fetches every page of a list endpoint, `page_size` items at a time,
+    /// following `nextPageToken` until the last page.
+    async fn list_all<P, T>(
+        &self,
+        operation: &'static str,
+        url: &str,
+        page_size: usize,
+        into_items: impl Fn(P) -> (Vec<T>, Option<String>),
+    ) -> Result<Vec<T>, GeminiError>
+    where
+        P: serde::de::DeserializeOwned,
+    {
+        let mut items = vec![];
+        let mut next_page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .request(reqwest::Method::GET, url)
+                .query(&[("pageSize", page_size)]);
+            if let Some(page_token) = &next_page_token {
+                request = request.query(&[("pageToken", page_token)]);
+            }
+
+            let (page, page_token) = into_items(self.send_parsed(operation, request).await?);
+            items.extend(page);
+            next_page_token = page_token;
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    fn api_url(&self) -> String {
+        match &self.api_url {
+            Some(api_url) => api_url.clone(),
+            None => format!("{}/{}", self.base_url, self.api_version.as_str()),
+        }
+    }
+
+    fn upload_url(&self) -> String {
+        let Some(api_url) = &self.api_url else {
+            return format!(
+                "{}/upload/{}/files",
+                self.base_url,
+                self.api_version.as_str()
+            );
+        };
+        match reqwest::Url::parse(api_url) {
+            Ok(mut url) => {
+                let path = format!("/upload{}/files", url.path().trim_end_matches('/'));
+                url.set_path(&path);
+                url.to_string()
+            }
+            Err(_) => format!("{api_url}/files"),
+        }
+    }
+
+    /// List all available models.
+    pub async fn list_models(&self) -> Result<Vec<types::Model>, GeminiError> {
         let _span = crate::telemetry::telemetry_span_guard!(
             info,
             "gemini_client_rs.list_models",
@@ -121,137 +888,482 @@ impl GeminiClient {
         let mut next_page_token = None;
         let mut page_fetch_count = 0usize;
         loop {
-            let mut url = format!("{}/models?key={}&pageSize=1000", self.api_url, self.api_key);
-            if let Some(ref next_page_token) = next_page_token {
-                url.push_str(&format!("&pageToken={next_page_token}"));
+            page_fetch_count += 1;
+            let page = self
+                .fetch_models_page(next_page_token.as_deref(), page_fetch_count)
+                .await?;
+
+            models.extend(page.models);
+            next_page_token = page.next_page_token;
+            if next_page_token.is_none() {
+                break;
+            }
+        }
+
+        crate::telemetry::telemetry_info!(
+            page_fetch_count,
+            model_count = models.len(),
+            "list_models completed"
+        );
+
+        Ok(models)
+    }
+
+    /// Lists available models, fetching pages lazily as the stream is
+    /// consumed.
+    ///
+    /// Unlike [`GeminiClient::list_models`], only one page is buffered at a
+    /// time, and dropping the stream stops further page requests.
+    pub fn list_models_paged(&self) -> GeminiModelStream {
+        let client = self.clone();
+
+        let stream = async_stream::stream! {
+            let _span = crate::telemetry::telemetry_span_guard!(
+                info,
+                "gemini_client_rs.list_models_paged",
+                has_api_key = !client.api_key.is_empty()
+            );
+            crate::telemetry::telemetry_info!("list_models_paged started");
+
+            let mut next_page_token: Option<String> = None;
+            let mut page_fetch_count = 0usize;
+            loop {
+                page_fetch_count += 1;
+                let page = match client
+                    .fetch_models_page(next_page_token.as_deref(), page_fetch_count)
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(error) => {
+                        yield Err(error);
+                        break;
+                    }
+                };
+
+                for model in page.models {
+                    yield Ok(model);
+                }
+
+                next_page_token = page.next_page_token;
+                if next_page_token.is_none() {
+                    break;
+                }
             }
 
-            page_fetch_count += 1;
-            crate::telemetry::telemetry_debug!(
+            crate::telemetry::telemetry_info!(
                 page_fetch_count,
-                has_page_token = next_page_token.is_some(),
-                "list_models fetching page"
+                "list_models_paged completed"
             );
+            let _ = page_fetch_count;
+        };
 
-            let response = match self.http_client.get(&url).send().await {
-                Ok(response) => response,
+        Box::pin(stream)
+    }
+
+    /// Gets a model's metadata, e.g. its token limits and default sampling
+    /// parameters.
+    pub async fn get_model(&self, model: &str) -> Result<types::Model, GeminiError> {
+        let url = format!("{}/{}", self.api_url(), model_resource(model));
+
+        let response = self
+            .execute(self.request(reqwest::Method::GET, &url))
+            .await?;
+        if !response.status().is_success() {
+            return Err(self.error_from_response(response, None).await);
+        }
+
+        let mut model: types::Model = self.parse_response(response).await?;
+        model.base_model_id = model.name.replace("models/", "");
+        Ok(model)
+    }
+
+    /// Checks that the API is reachable and the API key is accepted, by
+    /// listing a single model.
+    ///
+    /// A rejected key is reported as [`GeminiError::InvalidApiKey`], so
+    /// services can fail fast at startup.
+    pub async fn health_check(&self) -> Result<(), GeminiError> {
+        let _span = crate::telemetry::telemetry_span_guard!(
+            info,
+            "gemini_client_rs.health_check",
+            has_api_key = !self.api_key.is_empty()
+        );
+
+        let url = format!("{}/models", self.api_url());
+        let request = self
+            .request(reqwest::Method::GET, &url)
+            .query(&[("pageSize", "1")]);
+        let response = self.execute(request).await?;
+
+        let status = response.status().as_u16();
+        let body = GeminiError::read_body(response, self.max_response_bytes).await?;
+        // An unknown key is reported as 400 `API_KEY_INVALID` rather than 401.
+        if matches!(status, 401 | 403) || (status == 400 && body.contains("API_KEY_INVALID")) {
+            let error = GeminiError::InvalidApiKey(
+                serde_json::from_str(&body).unwrap_or(Value::String(body)),
+            );
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                "health_check rejected API key"
+            );
+            return Err(error);
+        }
+        if !(200..300).contains(&status) {
+            let error = GeminiError::from_status(status, body, None);
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                "health_check API failure"
+            );
+            return Err(error);
+        }
+
+        crate::telemetry::telemetry_info!("health_check succeeded");
+        Ok(())
+    }
+
+    async fn fetch_models_page(
+        &self,
+        page_token: Option<&str>,
+        page_fetch_count: usize,
+    ) -> Result<types::ListModelsResponse, GeminiError> {
+        let url = format!("{}/models", self.api_url());
+        let mut request = self
+            .request(reqwest::Method::GET, &url)
+            .query(&[("pageSize", "1000")]);
+        if let Some(page_token) = page_token {
+            request = request.query(&[("pageToken", page_token)]);
+        }
+
+        crate::telemetry::telemetry_debug!(
+            page_fetch_count,
+            has_page_token = page_token.is_some(),
+            "list_models fetching page"
+        );
+        let _ = page_fetch_count;
+
+        let response = match self.execute(request).await {
+            Ok(response) => response,
+            Err(error) => {
+                crate::telemetry::telemetry_error!(
+                    error_kind = crate::telemetry::gemini_error_kind(&error),
+                    page_fetch_count,
+                    "list_models request failed"
+                );
+                return Err(error);
+            }
+        };
+        if !response.status().is_success() {
+            let error = self.error_from_response(response, None).await;
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                page_fetch_count,
+                "list_models API failure"
+            );
+            return Err(error);
+        }
+
+        let mut page: types::ListModelsResponse = match self.parse_response(response).await {
+            Ok(page) => page,
+            Err(error) => {
+                crate::telemetry::telemetry_error!(
+                    error_kind = crate::telemetry::gemini_error_kind(&error),
+                    page_fetch_count,
+                    "list_models response parsing failed"
+                );
+                return Err(error);
+            }
+        };
+
+        for model in &mut page.models {
+            model.base_model_id = model.name.replace("models/", "");
+        }
+
+        Ok(page)
+    }
+
+    pub async fn generate_content(
+        &self,
+        model: &str,
+        request: &GenerateContentRequest,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        self.generate_content_until(model, request, None, None)
+            .await
+    }
+
+    /// Runs [`GeminiClient::generate_content`] for each request with at most
+    /// `concurrency` requests in flight, returning the results in input
+    /// order. A `concurrency` of 0 is treated as 1.
+    pub async fn generate_many(
+        &self,
+        model: &str,
+        requests: Vec<GenerateContentRequest>,
+        concurrency: usize,
+    ) -> Vec<Result<GenerateContentResponse, GeminiError>> {
+        futures_util::stream::iter(requests)
+            .map(|request| async move { self.generate_content(model, &request).await })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Generates content from a cache created with
+    /// [`CachesClient::create`], sending only the new `contents`.
+    ///
+    /// The system instruction, tools and turns stored in the cache must not
+    /// be resent, so the request carries nothing else. `model` must be the
+    /// model the cache was created for.
+    pub async fn generate_with_cache(
+        &self,
+        model: &str,
+        cache_name: &str,
+        contents: Vec<types::Content>,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        let request = GenerateContentRequest {
+            contents,
+            cached_content: Some(cache_name.to_string()),
+            ..Default::default()
+        };
+        self.generate_content(model, &request).await
+    }
+
+    /// Sends `body` to `generateContent` as-is and returns the raw JSON
+    /// response, e.g. to try API features the typed structs do not model
+    /// yet.
+    ///
+    /// Interceptors and request validation are skipped.
+    pub async fn generate_content_raw(
+        &self,
+        model: &str,
+        body: Value,
+    ) -> Result<Value, GeminiError> {
+        let url = format!(
+            "{}/{}:generateContent",
+            self.api_url(),
+            model_resource(model)
+        );
+
+        let (status, body) = self
+            .send_json(&url, &body, &reqwest::header::HeaderMap::new())
+            .await?;
+        if !(200..300).contains(&status) {
+            return Err(GeminiError::from_status(status, body, None));
+        }
+        GeminiError::parse_body(body)
+    }
+
+    /// Like [`GeminiClient::generate_content`], but gives up with
+    /// [`GeminiError::Timeout`] once `deadline` passes.
+    ///
+    /// This lets a caller cap the Gemini call by an overall request budget
+    /// rather than a fixed per-client timeout.
+    pub async fn generate_content_with_deadline(
+        &self,
+        model: &str,
+        request: &GenerateContentRequest,
+        deadline: std::time::Instant,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        self.generate_content_until(model, request, Some(deadline), None)
+            .await
+    }
+
+    /// Like [`GeminiClient::generate_content`], but sends `headers` with
+    /// this request only, e.g. `x-goog-user-project` to bill a specific
+    /// project or a tracing header.
+    pub async fn generate_content_with_headers(
+        &self,
+        model: &str,
+        request: &GenerateContentRequest,
+        headers: reqwest::header::HeaderMap,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        self.generate_content_until(model, request, None, Some(headers))
+            .await
+    }
+
+    async fn generate_content_until(
+        &self,
+        model: &str,
+        request: &GenerateContentRequest,
+        deadline: Option<std::time::Instant>,
+        headers: Option<reqwest::header::HeaderMap>,
+    ) -> Result<GenerateContentResponse, GeminiError> {
+        let span = crate::telemetry::telemetry_span_guard!(
+            info,
+            "gemini_client_rs.generate_content",
+            model,
+            contents_count = request.contents.len(),
+            tools_count = request.tools.len(),
+            has_system_instruction = request.system_instruction.is_some(),
+            has_generation_config = request.generation_config.is_some(),
+            status_code = tracing::field::Empty,
+            prompt_token_count = tracing::field::Empty,
+            candidates_token_count = tracing::field::Empty,
+            total_token_count = tracing::field::Empty
+        );
+
+        span.instrument(async {
+            crate::telemetry::telemetry_info!("generate_content started");
+
+            let request = self.intercept_request(request);
+            if let Err(error) = request.validate() {
+                crate::telemetry::telemetry_error!(
+                    error_kind = crate::telemetry::gemini_error_kind(&error),
+                    "generate_content request invalid"
+                );
+                return Err(error);
+            }
+
+            let url = format!(
+                "{}/{}:generateContent",
+                self.api_url(),
+                model_resource(model)
+            );
+
+            let headers = headers.unwrap_or_default();
+            let send = self.send_json(&url, &*request, &headers);
+            let sent = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                    if remaining.is_zero() {
+                        let error = GeminiError::Timeout;
+                        crate::telemetry::telemetry_error!(
+                            error_kind = crate::telemetry::gemini_error_kind(&error),
+                            "generate_content deadline exceeded before sending"
+                        );
+                        return Err(error);
+                    }
+                    tokio::time::timeout(remaining, send)
+                        .await
+                        .unwrap_or(Err(GeminiError::Timeout))
+                }
+                None => send.await,
+            };
+
+            let (status, body) = match sent {
+                Ok(sent) => sent,
                 Err(error) => {
-                    let error = GeminiError::Http(error);
                     crate::telemetry::telemetry_error!(
                         error_kind = crate::telemetry::gemini_error_kind(&error),
-                        page_fetch_count,
-                        "list_models request failed"
+                        "generate_content request failed"
                     );
                     return Err(error);
                 }
             };
-            if !response.status().is_success() {
-                let error = GeminiError::from_response(response, None).await;
+            span.record_status(status);
+            if !(200..300).contains(&status) {
+                let error = GeminiError::from_status(status, body, None);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
-                    page_fetch_count,
-                    "list_models API failure"
+                    "generate_content API failure"
                 );
                 return Err(error);
             }
 
-            let response: Response = match response.json().await {
+            let mut response: GenerateContentResponse = match GeminiError::parse_body(body) {
                 Ok(response) => response,
                 Err(error) => {
-                    let error = GeminiError::Http(error);
                     crate::telemetry::telemetry_error!(
                         error_kind = crate::telemetry::gemini_error_kind(&error),
-                        page_fetch_count,
-                        "list_models response parsing failed"
+                        "generate_content response parsing failed"
                     );
                     return Err(error);
                 }
             };
-
-            models.extend(response.models);
-            next_page_token = response.next_page_token;
-            if next_page_token.is_none() {
-                break;
+            for interceptor in &self.interceptors {
+                interceptor.after(&mut response);
+            }
+            if let Some(usage_metadata) = &response.usage_metadata {
+                span.record_usage(usage_metadata);
             }
-        }
-
-        let models = models
-            .into_iter()
-            .map(|mut model| {
-                model.base_model_id = model.name.replace("models/", "");
-                model
-            })
-            .collect::<Vec<_>>();
-        let _ = page_fetch_count;
 
-        crate::telemetry::telemetry_info!(
-            page_fetch_count,
-            model_count = models.len(),
-            "list_models completed"
-        );
+            crate::telemetry::telemetry_info!(
+                candidate_count = response.candidates.len(),
+                "generate_content completed"
+            );
 
-        Ok(models)
+            Ok(response)
+        })
+        .await
     }
 
-    pub async fn generate_content(
+    /// Counts the tokens `request` would consume when sent to `model`.
+    pub async fn count_tokens(
         &self,
         model: &str,
         request: &GenerateContentRequest,
-    ) -> Result<GenerateContentResponse, GeminiError> {
-        let _span = crate::telemetry::telemetry_span_guard!(
+    ) -> Result<CountTokensResponse, GeminiError> {
+        let span = crate::telemetry::telemetry_span_guard!(
             info,
-            "gemini_client_rs.generate_content",
+            "gemini_client_rs.count_tokens",
             model,
-            contents_count = request.contents.len(),
-            tools_count = request.tools.len(),
-            has_system_instruction = request.system_instruction.is_some(),
-            has_generation_config = request.generation_config.is_some()
+            contents_count = request.contents.len(),
+            status_code = tracing::field::Empty,
+            total_tokens = tracing::field::Empty
         );
-        crate::telemetry::telemetry_info!("generate_content started");
 
-        let url = format!(
-            "{}/models/{model}:generateContent?key={}",
-            self.api_url, self.api_key
-        );
+        span.instrument(async {
+            crate::telemetry::telemetry_info!("count_tokens started");
 
-        let response = match self.http_client.post(&url).json(request).send().await {
-            Ok(response) => response,
-            Err(error) => {
-                let error = GeminiError::Http(error);
+            if let Err(error) = request.validate() {
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
-                    "generate_content request failed"
+                    "count_tokens request invalid"
                 );
                 return Err(error);
             }
-        };
-        if !response.status().is_success() {
-            let error = GeminiError::from_response(response, None).await;
-            crate::telemetry::telemetry_error!(
-                error_kind = crate::telemetry::gemini_error_kind(&error),
-                "generate_content API failure"
-            );
-            return Err(error);
-        }
 
-        let response: GenerateContentResponse = match response.json().await {
-            Ok(response) => response,
-            Err(error) => {
-                let error = GeminiError::Http(error);
+            let url = format!("{}/{}:countTokens", self.api_url(), model_resource(model));
+
+            let mut generate_content_request = match serde_json::to_value(request) {
+                Ok(value) => value,
+                Err(error) => {
+                    return Err(GeminiError::Json {
+                        data: String::new(),
+                        error,
+                    })
+                }
+            };
+            if let Some(object) = generate_content_request.as_object_mut() {
+                object.insert("model".to_string(), Value::String(model_resource(model)));
+            }
+            let body = serde_json::json!({ "generateContentRequest": generate_content_request });
+
+            let (status, body) = match self.send_json(&url, &body, &Default::default()).await {
+                Ok(sent) => sent,
+                Err(error) => {
+                    crate::telemetry::telemetry_error!(
+                        error_kind = crate::telemetry::gemini_error_kind(&error),
+                        "count_tokens request failed"
+                    );
+                    return Err(error);
+                }
+            };
+            span.record_status(status);
+            if !(200..300).contains(&status) {
+                let error = GeminiError::from_status(status, body, None);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
-                    "generate_content response parsing failed"
+                    "count_tokens API failure"
                 );
                 return Err(error);
             }
-        };
 
-        crate::telemetry::telemetry_info!(
-            candidate_count = response.candidates.len(),
-            "generate_content completed"
-        );
+            let response: CountTokensResponse = match GeminiError::parse_body(body) {
+                Ok(response) => response,
+                Err(error) => {
+                    crate::telemetry::telemetry_error!(
+                        error_kind = crate::telemetry::gemini_error_kind(&error),
+                        "count_tokens response parsing failed"
+                    );
+                    return Err(error);
+                }
+            };
+            span.record("total_tokens", response.total_tokens);
 
-        Ok(response)
+            crate::telemetry::telemetry_info!("count_tokens completed");
+
+            Ok(response)
+        })
+        .await
     }
 
     /// Generates a streamed response from the model given an input
@@ -267,6 +1379,9 @@ impl GeminiClient {
 
     /// Generates a streamed response from the model given an input
     /// [`GenerateContentRequest`].
+    ///
+    /// The request is sent lazily on the first poll. To stop early, simply
+    /// drop the returned stream; see [`GeminiResponseStream`].
     pub async fn stream_generate_content(
         &self,
         model: &str,
@@ -278,16 +1393,26 @@ impl GeminiClient {
         let _has_system_instruction = request.system_instruction.is_some();
         let _has_generation_config = request.generation_config.is_some();
         let url = format!(
-            "{}/models/{model}:streamGenerateContent?alt=sse&key={}",
-            self.api_url, self.api_key
+            "{}/{}:streamGenerateContent?alt=sse",
+            self.api_url(),
+            model_resource(model)
         );
 
-        let mut stream = self
-            .http_client
-            .post(&url)
-            .json(request)
-            .eventsource()
-            .expect("can clone request builder");
+        let request = self.intercept_request(request);
+        if let Err(error) = request.validate() {
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                "stream_content request invalid"
+            );
+            return Err(error);
+        }
+        let request = self
+            .request(reqwest::Method::POST, &url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&*request);
+        let client = self.clone();
+        let mut retry_attempt = 0usize;
+        let mut retries_left = self.stream_retries;
 
         let stream = async_stream::stream! {
             let _span = crate::telemetry::telemetry_span_guard!(
@@ -297,22 +1422,105 @@ impl GeminiClient {
                 contents_count = _contents_count,
                 tools_count = _tools_count,
                 has_system_instruction = _has_system_instruction,
-                has_generation_config = _has_generation_config
+                has_generation_config = _has_generation_config,
+                retry_attempt = 0usize
             );
             crate::telemetry::telemetry_info!("stream_content started");
             let mut message_count = 0usize;
 
-            while let Some(event) = stream.next().await {
-                match event {
-                    Ok(event) => match event {
-                        Event::Open => crate::telemetry::telemetry_debug!("stream_content opened"),
-                        Event::Message(event) => {
+            // The API has no way to resume a stream, so each attempt resends
+            // the request and is only made before the first message.
+            loop {
+                let request = request.try_clone().expect("JSON request can be cloned");
+                let error = match client.execute(request).await {
+                    Ok(response) if !response.status().is_success() => {
+                        let error = client
+                            .error_from_response(
+                                response,
+                                Some(serde_json::json!({"cause": "Invalid status code"})),
+                            )
+                            .await;
+                        crate::telemetry::telemetry_error!(
+                            error_kind = crate::telemetry::gemini_error_kind(&error),
+                            message_count,
+                            "stream_content invalid status code"
+                        );
+                        yield Err(error);
+                        break;
+                    }
+                    Ok(response) if !is_event_stream(&response) => {
+                        let header = response
+                            .headers()
+                            .get(reqwest::header::CONTENT_TYPE)
+                            .and_then(|content_type| content_type.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string();
+                        let error = client
+                            .error_from_response(
+                                response,
+                                Some(serde_json::json!({
+                                    "cause": "Invalid content type",
+                                    "header": header
+                                })),
+                            )
+                            .await;
+                        crate::telemetry::telemetry_error!(
+                            error_kind = crate::telemetry::gemini_error_kind(&error),
+                            message_count,
+                            "stream_content invalid content type"
+                        );
+                        yield Err(error);
+                        break;
+                    }
+                    Ok(response) => {
+                        crate::telemetry::telemetry_debug!("stream_content opened");
+                        // The limit applies to the whole stream, so an endpoint
+                        // can't stream an unbounded body one event at a time.
+                        let mut received = 0usize;
+                        let max_response_bytes = client.max_response_bytes;
+                        let mut events = response
+                            .bytes_stream()
+                            .map(move |chunk| {
+                                let chunk = chunk.map_err(GeminiError::from_reqwest)?;
+                                received = received.saturating_add(chunk.len());
+                                match max_response_bytes {
+                                    Some(limit) if received > limit => {
+                                        Err(GeminiError::ResponseTooLarge { limit })
+                                    }
+                                    _ => Ok(chunk),
+                                }
+                            })
+                            .eventsource();
+                        let mut failure = None;
+                        while let Some(event) = events.next().await {
+                            use eventsource_stream::EventStreamError;
+                            let event = match event {
+                                Ok(event) => event,
+                                Err(EventStreamError::Transport(error)) => {
+                                    failure = Some(error);
+                                    break;
+                                }
+                                Err(EventStreamError::Utf8(error)) => {
+                                    failure = Some(reqwest_eventsource::Error::Utf8(error).into());
+                                    break;
+                                }
+                                Err(EventStreamError::Parser(error)) => {
+                                    failure = Some(reqwest_eventsource::Error::Parser(error).into());
+                                    break;
+                                }
+                            };
                             message_count += 1;
                             crate::telemetry::telemetry_debug!(
                                 message_count,
                                 "stream_content message received"
                             );
                             yield serde_json::from_str::<types::GenerateContentResponse>(&event.data)
+                                .map(|mut response| {
+                                    for interceptor in &client.interceptors {
+                                        interceptor.after(&mut response);
+                                    }
+                                    response
+                                })
                                 .map_err(|error| {
                                     let error = GeminiError::Json {
                                         data: event.data,
@@ -326,54 +1534,40 @@ impl GeminiClient {
                                     error
                                 })
                         }
-                    },
-                    Err(e) => match e {
-                        reqwest_eventsource::Error::StreamEnded => {
-                            crate::telemetry::telemetry_info!(
-                                message_count,
-                                "stream_content ended"
-                            );
-                            stream.close()
-                        }
-                        reqwest_eventsource::Error::InvalidContentType(content_type, response) => {
-                            let header = content_type.to_str().unwrap_or_default();
-                            let error = GeminiError::from_response(
-                                response,
-                                Some(serde_json::json!({
-                                    "cause": "Invalid content type",
-                                    "header": header
-                                })),
-                            ).await;
-                            crate::telemetry::telemetry_error!(
-                                error_kind = crate::telemetry::gemini_error_kind(&error),
-                                message_count,
-                                "stream_content invalid content type"
-                            );
-                            yield Err(error)
-                        }
-                        reqwest_eventsource::Error::InvalidStatusCode(_, response) => {
-                            let error = GeminiError::from_response(
-                                response,
-                                Some(serde_json::json!({"cause": "Invalid status code"})),
-                            ).await;
-                            crate::telemetry::telemetry_error!(
-                                error_kind = crate::telemetry::gemini_error_kind(&error),
-                                message_count,
-                                "stream_content invalid status code"
-                            );
-                            yield Err(error)
-                        }
-                        _ => {
-                            let error = GeminiError::EventSource(e);
-                            crate::telemetry::telemetry_error!(
-                                error_kind = crate::telemetry::gemini_error_kind(&error),
-                                message_count,
-                                "stream_content event source failure"
-                            );
-                            yield Err(error)
+                        match failure {
+                            Some(error) => error,
+                            None => {
+                                crate::telemetry::telemetry_info!(
+                                    message_count,
+                                    "stream_content ended"
+                                );
+                                break;
+                            }
                         }
                     }
+                    Err(error) => error,
+                };
+
+                let too_large = matches!(error, GeminiError::ResponseTooLarge { .. });
+                if message_count == 0 && retries_left > 0 && !too_large {
+                    retries_left -= 1;
+                    retry_attempt += 1;
+                    _span.record("retry_attempt", retry_attempt);
+                    crate::telemetry::telemetry_debug!(
+                        retry_attempt,
+                        retries_left,
+                        error = %error,
+                        "stream_content connection failed, retrying"
+                    );
+                    continue;
                 }
+                crate::telemetry::telemetry_error!(
+                    error_kind = crate::telemetry::gemini_error_kind(&error),
+                    message_count,
+                    "stream_content event source failure"
+                );
+                yield Err(error);
+                break;
             }
 
             crate::telemetry::telemetry_info!(
@@ -386,6 +1580,159 @@ impl GeminiClient {
         Ok(Box::pin(stream))
     }
 
+    /// Streams a conversation in which the model may call functions.
+    ///
+    /// Each model turn is streamed, yielding text as
+    /// [`FunctionCallingEvent::TextDelta`]s. Whether a turn calls functions
+    /// is only known once it arrives, so function calling turns are streamed
+    /// too rather than sent again, and any text the model writes before its
+    /// calls is yielded as well. When a turn ends with function calls, each
+    /// one is passed to `handler`, whose future is awaited, and its result
+    /// sent back to the model, which then continues in a new turn.
+    ///
+    /// ```rust,no_run
+    /// # use futures_util::StreamExt;
+    /// # use gemini_client_rs::types::GenerateContentRequest;
+    /// # use gemini_client_rs::{gemini_tool, GeminiClient};
+    /// /// Looks up a user's name by id.
+    /// #[gemini_tool]
+    /// async fn find_user(id: i32) -> String {
+    ///     format!("user {id}")
+    /// }
+    ///
+    /// # async fn run(client: GeminiClient, request: GenerateContentRequest) {
+    /// let mut events = client.stream_generate_content_with_function_calling(
+    ///     "gemini-2.5-flash",
+    ///     request,
+    ///     5,
+    ///     |call| async move { FindUserTool::call(&call).await },
+    /// );
+    /// while let Some(event) = events.next().await {
+    ///     println!("{event:?}");
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// The stream ends after the first turn without function calls, or
+    /// after the first error, including a [`GeminiError::FunctionExecution`]
+    /// from `handler`. If the model still calls functions in turn
+    /// `max_turns`, the calls are not executed and the stream ends with
+    /// [`GeminiError::TooManyTurns`].
+    ///
+    /// `request` is taken by value and extended in place with each turn, so
+    /// large parts such as inline images are not copied between turns,
+    /// unless an [`Interceptor`] modifies the request, which copies it once
+    /// per turn.
+    pub fn stream_generate_content_with_function_calling<F, Fut>(
+        &self,
+        model: &str,
+        request: GenerateContentRequest,
+        max_turns: usize,
+        handler: F,
+    ) -> GeminiFunctionCallingStream
+    where
+        F: Fn(types::FunctionCall) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = types::FunctionResult> + Send + 'static,
+    {
+        let client = self.clone();
+        let model = model.to_string();
+
+        let stream = async_stream::stream! {
+            let mut request = request;
+            for turn_number in 1.. {
+                let mut chunks = match client.stream_generate_content(&model, &request).await {
+                    Ok(chunks) => chunks,
+                    Err(error) => {
+                        yield Err(error);
+                        return;
+                    }
+                };
+
+                let mut turn: Option<GenerateContentResponse> = None;
+                while let Some(chunk) = chunks.next().await {
+                    let chunk = match chunk {
+                        Ok(chunk) => chunk,
+                        Err(error) => {
+                            yield Err(error);
+                            return;
+                        }
+                    };
+                    let deltas: Vec<String> = chunk
+                        .candidates
+                        .first()
+                        .and_then(|candidate| candidate.content.as_ref())
+                        .into_iter()
+                        .flat_map(|content| content.parts.iter())
+                        .filter_map(|part| match part {
+                            types::Part::Text { text } => Some(text.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    for delta in deltas {
+                        yield Ok(FunctionCallingEvent::TextDelta(delta));
+                    }
+                    match &mut turn {
+                        Some(turn) => turn.merge(chunk),
+                        None => turn = Some(chunk),
+                    }
+                }
+
+                let Some(content) = turn
+                    .and_then(|turn| turn.candidates.into_iter().next())
+                    .and_then(|candidate| candidate.content)
+                else {
+                    return;
+                };
+                // Borrow the calls from the turn, which is moved into the
+                // history below, so only the events own copies of them.
+                let calls_functions = content
+                    .parts
+                    .iter()
+                    .any(|part| matches!(part, types::Part::FunctionCall { .. }));
+                if calls_functions && turn_number >= max_turns {
+                    yield Err(GeminiError::TooManyTurns { max_turns });
+                    return;
+                }
+                let calls = content.parts.iter().filter_map(|part| match part {
+                    types::Part::FunctionCall { call } => Some(call),
+                    _ => None,
+                });
+
+                let mut responses = Vec::new();
+                for call in calls {
+                    yield Ok(FunctionCallingEvent::ToolCall(call.clone()));
+                    match handler(call.clone()).await {
+                        Ok(result) => {
+                            let response = call.respond(result);
+                            yield Ok(FunctionCallingEvent::ToolResult(response.clone()));
+                            responses.push(response);
+                        }
+                        Err(error) => {
+                            yield Err(GeminiError::FunctionExecution {
+                                name: call.name.clone(),
+                                message: error.to_string(),
+                            });
+                            return;
+                        }
+                    }
+                }
+                if responses.is_empty() {
+                    return;
+                }
+
+                // Replay the full model turn, including any thought
+                // signatures, ahead of the function results.
+                request.contents.push(types::Content {
+                    role: Some(types::Role::Model),
+                    ..content
+                });
+                request.contents.push(types::Content::function_responses(responses));
+            }
+        };
+
+        Box::pin(stream)
+    }
+
     /// Generates embeddings for the provided content.
     pub async fn embed_content(
         &self,
@@ -399,15 +1746,23 @@ impl GeminiClient {
         );
         crate::telemetry::telemetry_info!("embed_content started");
 
+        if let Err(error) = request.validate() {
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                "embed_content request invalid"
+            );
+            return Err(error);
+        }
+
         let url = format!(
-            "{}/models/{}:embedContent?key={}",
-            self.api_url, request.model, self.api_key
+            "{}/{}:embedContent",
+            self.api_url(),
+            model_resource(&request.model)
         );
 
-        let response = match self.http_client.post(&url).json(request).send().await {
-            Ok(response) => response,
+        let (status, body) = match self.send_json(&url, request, &Default::default()).await {
+            Ok(sent) => sent,
             Err(error) => {
-                let error = GeminiError::Http(error);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
                     "embed_content request failed"
@@ -416,8 +1771,8 @@ impl GeminiClient {
             }
         };
 
-        if !response.status().is_success() {
-            let error = GeminiError::from_response(response, None).await;
+        if !(200..300).contains(&status) {
+            let error = GeminiError::from_status(status, body, None);
             crate::telemetry::telemetry_error!(
                 error_kind = crate::telemetry::gemini_error_kind(&error),
                 "embed_content API failure"
@@ -425,10 +1780,9 @@ impl GeminiClient {
             return Err(error);
         }
 
-        let response: EmbedContentResponse = match response.json().await {
+        let response: EmbedContentResponse = match GeminiError::parse_body(body) {
             Ok(response) => response,
             Err(error) => {
-                let error = GeminiError::Http(error);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
                     "embed_content response parsing failed"
@@ -456,15 +1810,27 @@ impl GeminiClient {
         );
         crate::telemetry::telemetry_info!("batch_embed_contents started");
 
+        if let Err(error) = request
+            .requests
+            .iter()
+            .try_for_each(|request| request.validate())
+        {
+            crate::telemetry::telemetry_error!(
+                error_kind = crate::telemetry::gemini_error_kind(&error),
+                "batch_embed_contents request invalid"
+            );
+            return Err(error);
+        }
+
         let url = format!(
-            "{}/models/{}:batchEmbedContents?key={}",
-            self.api_url, model, self.api_key
+            "{}/{}:batchEmbedContents",
+            self.api_url(),
+            model_resource(model)
         );
 
-        let response = match self.http_client.post(&url).json(request).send().await {
-            Ok(response) => response,
+        let (status, body) = match self.send_json(&url, request, &Default::default()).await {
+            Ok(sent) => sent,
             Err(error) => {
-                let error = GeminiError::Http(error);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
                     "batch_embed_contents request failed"
@@ -473,8 +1839,8 @@ impl GeminiClient {
             }
         };
 
-        if !response.status().is_success() {
-            let error = GeminiError::from_response(response, None).await;
+        if !(200..300).contains(&status) {
+            let error = GeminiError::from_status(status, body, None);
             crate::telemetry::telemetry_error!(
                 error_kind = crate::telemetry::gemini_error_kind(&error),
                 "batch_embed_contents API failure"
@@ -482,10 +1848,9 @@ impl GeminiClient {
             return Err(error);
         }
 
-        let response: BatchEmbedContentsResponse = match response.json().await {
+        let response: BatchEmbedContentsResponse = match GeminiError::parse_body(body) {
             Ok(response) => response,
             Err(error) => {
-                let error = GeminiError::Http(error);
                 crate::telemetry::telemetry_error!(
                     error_kind = crate::telemetry::gemini_error_kind(&error),
                     "batch_embed_contents response parsing failed"
@@ -499,10 +1864,357 @@ impl GeminiClient {
         Ok(response)
     }
 
+    /// Embeds a stream of texts of any length, yielding one embedding per
+    /// text in input order.
+    ///
+    /// Texts are grouped into `batchEmbedContents` requests of `batch_size`,
+    /// capped at the API limit of [`GeminiClient::MAX_BATCH_EMBED_SIZE`],
+    /// with at most `concurrency` requests in flight. A failed batch yields
+    /// a [`GeminiError::EmbeddingBatchFailed`] for each of its texts, so the
+    /// stream always has one item per text, and continues with the next
+    /// batch. A batch whose response has the wrong number of embeddings
+    /// fails with [`GeminiError::EmbeddingCountMismatch`].
+    pub fn embed_corpus<S>(
+        &self,
+        model: &str,
+        texts: S,
+        batch_size: usize,
+        concurrency: usize,
+    ) -> GeminiEmbeddingStream
+    where
+        S: Stream<Item = String> + Send + 'static,
+    {
+        let client = self.clone();
+        let model = model.to_string();
+        let batch_size = batch_size.clamp(1, Self::MAX_BATCH_EMBED_SIZE);
+
+        let stream = texts
+            .chunks(batch_size)
+            .enumerate()
+            .map(move |(batch_index, batch)| {
+                let client = client.clone();
+                let model = model.clone();
+                async move {
+                    let start = batch_index * batch_size;
+                    let len = batch.len();
+                    let request = BatchEmbedContentsRequest {
+                        requests: batch
+                            .into_iter()
+                            .map(|text| EmbedContentRequest {
+                                model: model_resource(&model),
+                                content: types::Content {
+                                    parts: vec![types::Part::text(text)],
+                                    role: None,
+                                },
+                                task_type: None,
+                                title: None,
+                                output_dimensionality: None,
+                            })
+                            .collect(),
+                    };
+                    let result = client
+                        .batch_embed_contents(&model, &request)
+                        .await
+                        .and_then(|response| {
+                            if response.embeddings.len() == len {
+                                Ok(response.embeddings)
+                            } else {
+                                Err(GeminiError::EmbeddingCountMismatch {
+                                    expected: len,
+                                    actual: response.embeddings.len(),
+                                })
+                            }
+                        });
+                    let embeddings: Vec<Result<types::ContentEmbedding, GeminiError>> = match result
+                    {
+                        Ok(embeddings) => embeddings.into_iter().map(Ok).collect(),
+                        Err(error) => {
+                            let error = std::sync::Arc::new(error);
+                            (start..start + len)
+                                .map(|index| {
+                                    Err(GeminiError::EmbeddingBatchFailed {
+                                        index,
+                                        source: error.clone(),
+                                    })
+                                })
+                                .collect()
+                        }
+                    };
+                    futures_util::stream::iter(embeddings)
+                }
+            })
+            .buffered(concurrency.max(1))
+            .flatten();
+
+        Box::pin(stream)
+    }
+
+    /// Starts a multi-turn conversation with `model` that keeps its own
+    /// history.
+    pub fn chat(&self, model: &str) -> ChatSession {
+        ChatSession::new(self.clone(), model)
+    }
+
     /// Access the Files API client.
     pub fn files(&self) -> FilesClient<'_> {
         FilesClient { client: self }
     }
+
+    /// Access the model tuning API client.
+    pub fn tuned_models(&self) -> TunedModelsClient<'_> {
+        TunedModelsClient { client: self }
+    }
+
+    /// Access the Semantic Retriever API client.
+    pub fn corpora(&self) -> CorporaClient<'_> {
+        CorporaClient { client: self }
+    }
+
+    /// Access the context caching API client.
+    pub fn caches(&self) -> CachesClient<'_> {
+        CachesClient { client: self }
+    }
+}
+
+pub struct CorporaClient<'a> {
+    client: &'a GeminiClient,
+}
+
+impl<'a> CorporaClient<'a> {
+    /// Creates an empty corpus.
+    pub async fn create_corpus(
+        &self,
+        corpus: &types::Corpus,
+    ) -> Result<types::Corpus, GeminiError> {
+        let url = format!("{}/corpora", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .json(corpus);
+        self.client.send_parsed("corpora.create", request).await
+    }
+
+    /// Lists all corpora owned by the caller.
+    pub async fn list_corpora(&self) -> Result<Vec<types::Corpus>, GeminiError> {
+        let url = format!("{}/corpora", self.client.api_url());
+        self.client
+            .list_all(
+                "corpora.list",
+                &url,
+                20,
+                |page: types::ListCorporaResponse| (page.corpora, page.next_page_token),
+            )
+            .await
+    }
+
+    /// Deletes a corpus, e.g. `corpora/123`. With `force`, any documents
+    /// and chunks it contains are deleted too; otherwise deleting a
+    /// non-empty corpus fails.
+    pub async fn delete_corpus(&self, name: &str, force: bool) -> Result<(), GeminiError> {
+        self.delete("corpora.delete", name, force).await
+    }
+
+    /// Creates an empty document in `corpus`, e.g. `corpora/123`.
+    pub async fn create_document(
+        &self,
+        corpus: &str,
+        document: &types::Document,
+    ) -> Result<types::Document, GeminiError> {
+        let url = format!("{}/{corpus}/documents", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .json(document);
+        self.client.send_parsed("documents.create", request).await
+    }
+
+    /// Lists all documents in `corpus`.
+    pub async fn list_documents(&self, corpus: &str) -> Result<Vec<types::Document>, GeminiError> {
+        let url = format!("{}/{corpus}/documents", self.client.api_url());
+        self.client
+            .list_all(
+                "documents.list",
+                &url,
+                20,
+                |page: types::ListDocumentsResponse| (page.documents, page.next_page_token),
+            )
+            .await
+    }
+
+    /// Deletes a document, e.g. `corpora/123/documents/abc`. With `force`,
+    /// its chunks are deleted too.
+    pub async fn delete_document(&self, name: &str, force: bool) -> Result<(), GeminiError> {
+        self.delete("documents.delete", name, force).await
+    }
+
+    /// Creates a chunk in `document`, e.g. `corpora/123/documents/abc`.
+    ///
+    /// The chunk is embedded asynchronously and becomes searchable once its
+    /// state is [`types::ChunkState::StateActive`].
+    pub async fn create_chunk(
+        &self,
+        document: &str,
+        chunk: &types::Chunk,
+    ) -> Result<types::Chunk, GeminiError> {
+        let url = format!("{}/{document}/chunks", self.client.api_url());
+        let request = self.client.request(reqwest::Method::POST, &url).json(chunk);
+        self.client.send_parsed("chunks.create", request).await
+    }
+
+    /// Lists all chunks in `document`.
+    pub async fn list_chunks(&self, document: &str) -> Result<Vec<types::Chunk>, GeminiError> {
+        let url = format!("{}/{document}/chunks", self.client.api_url());
+        self.client
+            .list_all(
+                "chunks.list",
+                &url,
+                20,
+                |page: types::ListChunksResponse| (page.chunks, page.next_page_token),
+            )
+            .await
+    }
+
+    /// Deletes a chunk, e.g. `corpora/123/documents/abc/chunks/xyz`.
+    pub async fn delete_chunk(&self, name: &str) -> Result<(), GeminiError> {
+        self.delete("chunks.delete", name, false).await
+    }
+
+    /// Performs a semantic search over `corpus`, returning the most relevant
+    /// chunks first.
+    pub async fn query_corpus(
+        &self,
+        corpus: &str,
+        request: &types::QueryCorpusRequest,
+    ) -> Result<types::QueryCorpusResponse, GeminiError> {
+        let url = format!("{}/{corpus}:query", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .json(request);
+        self.client.send_parsed("corpora.query", request).await
+    }
+
+    async fn delete(
+        &self,
+        operation: &'static str,
+        name: &str,
+        force: bool,
+    ) -> Result<(), GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let mut request = self.client.request(reqwest::Method::DELETE, &url);
+        if force {
+            request = request.query(&[("force", "true")]);
+        }
+        self.client.send_checked(operation, request).await?;
+        Ok(())
+    }
+}
+
+pub struct TunedModelsClient<'a> {
+    client: &'a GeminiClient,
+}
+
+impl<'a> TunedModelsClient<'a> {
+    /// Starts a tuning job, returning the long-running operation that tracks
+    /// it. Poll it with [`TunedModelsClient::get_tuning_operation`].
+    ///
+    /// Once tuning completes, the tuned model name, e.g. `tunedModels/abc`,
+    /// can be passed to [`GeminiClient::generate_content`].
+    pub async fn create_tuned_model(
+        &self,
+        tuned_model: &types::TunedModel,
+    ) -> Result<types::Operation, GeminiError> {
+        let url = format!("{}/tunedModels", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .json(tuned_model);
+        self.client
+            .send_parsed("tuned_models.create", request)
+            .await
+    }
+
+    /// Gets the latest state of a tuning operation, e.g.
+    /// `tunedModels/abc/operations/xyz`.
+    pub async fn get_tuning_operation(&self, name: &str) -> Result<types::Operation, GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::GET, &url);
+        self.client
+            .send_parsed("tuned_models.get_operation", request)
+            .await
+    }
+
+    /// Gets a tuned model, e.g. `tunedModels/abc`.
+    pub async fn get_tuned_model(&self, name: &str) -> Result<types::TunedModel, GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::GET, &url);
+        self.client.send_parsed("tuned_models.get", request).await
+    }
+
+    /// Lists all tuned models, following `nextPageToken` until every page
+    /// has been fetched.
+    pub async fn list_tuned_models(&self) -> Result<Vec<types::TunedModel>, GeminiError> {
+        let url = format!("{}/tunedModels", self.client.api_url());
+        self.client
+            .list_all(
+                "tuned_models.list",
+                &url,
+                1000,
+                |page: types::ListTunedModelsResponse| (page.tuned_models, page.next_page_token),
+            )
+            .await
+    }
+}
+
+pub struct CachesClient<'a> {
+    client: &'a GeminiClient,
+}
+
+impl<'a> CachesClient<'a> {
+    /// Creates a cached content resource that later requests can reference
+    /// through [`GenerateContentRequest::cached_content`].
+    pub async fn create(
+        &self,
+        cached_content: &types::CachedContent,
+    ) -> Result<types::CachedContent, GeminiError> {
+        let url = format!("{}/cachedContents", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .json(cached_content);
+        self.client.send_parsed("caches.create", request).await
+    }
+
+    /// Reads a cached content resource, e.g. `cachedContents/abc123`.
+    pub async fn get(&self, name: &str) -> Result<types::CachedContent, GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::GET, &url);
+        self.client.send_parsed("caches.get", request).await
+    }
+
+    /// Refreshes the expiration of a cached content resource to `ttl` from
+    /// now.
+    pub async fn update_ttl(
+        &self,
+        name: &str,
+        ttl: std::time::Duration,
+    ) -> Result<types::CachedContent, GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self
+            .client
+            .request(reqwest::Method::PATCH, &url)
+            .query(&[("updateMask", "ttl")])
+            .json(&serde_json::json!({ "ttl": types::CachedContent::format_ttl(ttl) }));
+        self.client.send_parsed("caches.update_ttl", request).await
+    }
+
+    /// Deletes a cached content resource.
+    pub async fn delete(&self, name: &str) -> Result<(), GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::DELETE, &url);
+        self.client.send_checked("caches.delete", request).await?;
+        Ok(())
+    }
 }
 
 pub struct FilesClient<'a> {
@@ -540,13 +2252,99 @@ impl<'a> FilesClient<'a> {
         }
     }
 
+    /// Uploads a file and waits until it has finished processing.
+    ///
+    /// Files such as videos are `PROCESSING` after upload and can't be
+    /// referenced in requests until they become `ACTIVE`.
+    pub async fn upload_file_and_wait(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        options: types::FilePollOptions,
+    ) -> Result<types::File, GeminiError> {
+        let file = self.upload_file(path).await?;
+        if file.state == types::FileState::Active {
+            return Ok(file);
+        }
+        self.wait_until_active(&file.name, options).await
+    }
+
+    /// Polls a file until its state is `ACTIVE`.
+    ///
+    /// Returns [`GeminiError::FileProcessingFailed`] if processing fails, and
+    /// [`GeminiError::Timeout`] if the file is not active within
+    /// `options.timeout`.
+    pub async fn wait_until_active(
+        &self,
+        name: &str,
+        options: types::FilePollOptions,
+    ) -> Result<types::File, GeminiError> {
+        let deadline = tokio::time::Instant::now() + options.timeout;
+        loop {
+            let file = self.get_file(name).await?;
+            match file.state {
+                types::FileState::Active => return Ok(file),
+                types::FileState::Failed => {
+                    return Err(GeminiError::FileProcessingFailed(file.name))
+                }
+                _ => {}
+            }
+
+            if tokio::time::Instant::now() + options.poll_interval > deadline {
+                return Err(GeminiError::Timeout);
+            }
+            tokio::time::sleep(options.poll_interval).await;
+        }
+    }
+
+    /// Lists all files owned by the project, following `nextPageToken`
+    /// until every page has been fetched.
+    pub async fn list_files(&self) -> Result<Vec<types::File>, GeminiError> {
+        let url = format!("{}/files", self.client.api_url());
+        self.client
+            .list_all("files.list", &url, 100, |page: types::ListFilesResponse| {
+                (page.files, page.next_page_token)
+            })
+            .await
+    }
+
+    /// Deletes a file, e.g. `files/abc123`.
+    pub async fn delete_file(&self, name: &str) -> Result<(), GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::DELETE, &url);
+        self.client.send_checked("files.delete", request).await?;
+        Ok(())
+    }
+
+    /// Deletes every file that has expired or will expire within `margin`,
+    /// returning how many were deleted.
+    pub async fn delete_expired_files(
+        &self,
+        margin: std::time::Duration,
+    ) -> Result<usize, GeminiError> {
+        let mut deleted = 0;
+        for file in self.list_files().await? {
+            if file.expires_within(margin) {
+                self.delete_file(&file.name).await?;
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Gets the metadata of a file, e.g. `files/abc123`.
+    pub async fn get_file(&self, name: &str) -> Result<types::File, GeminiError> {
+        let url = format!("{}/{name}", self.client.api_url());
+        let request = self.client.request(reqwest::Method::GET, &url);
+        self.client.send_parsed("files.get", request).await
+    }
+
     async fn upload_multipart(
         &self,
         path: &std::path::Path,
         mime_type: &str,
         file_name: &str,
     ) -> Result<types::File, GeminiError> {
-        let url = "https://generativelanguage.googleapis.com/upload/v1beta/files";
+        let url = self.client.upload_url();
         let data = std::fs::read(path).map_err(|e| {
             GeminiError::Api(serde_json::json!({
                 "status": 500,
@@ -567,23 +2365,17 @@ impl<'a> FilesClient<'a> {
             )
             .part(
                 "file",
-                reqwest::multipart::Part::bytes(data).mime_str(mime_type)?,
-            );
-
-        let response = self
-            .client
-            .http_client
-            .post(url)
-            .query(&[("key", &self.client.api_key)])
-            .multipart(form)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(GeminiError::from_response(response, None).await);
-        }
+                reqwest::multipart::Part::bytes(data).mime_str(mime_type)?,
+            );
 
-        Ok(response.json().await?)
+        let request = self
+            .client
+            .request(reqwest::Method::POST, &url)
+            .multipart(form);
+        self.client
+            .send_parsed::<types::UploadFileResponse>("files.upload", request)
+            .await
+            .map(|response| response.file)
     }
 
     async fn upload_resumable(
@@ -593,28 +2385,24 @@ impl<'a> FilesClient<'a> {
         file_name: &str,
         size: u64,
     ) -> Result<types::File, GeminiError> {
-        let url = "https://generativelanguage.googleapis.com/upload/v1beta/files";
+        let url = self.client.upload_url();
 
         // 1. Initial request to get upload URL
-        let response = self
+        let request = self
             .client
-            .http_client
-            .post(url)
-            .query(&[("key", &self.client.api_key)])
+            .request(reqwest::Method::POST, &url)
             .header("X-Goog-Upload-Protocol", "resumable")
             .header("X-Goog-Upload-Command", "start")
             .header("X-Goog-Upload-Header-Content-Length", size)
             .header("X-Goog-Upload-Header-Content-Type", mime_type)
             .json(&serde_json::json!({
                 "file": { "display_name": file_name }
-            }))
-            .send()
+            }));
+        let response = self
+            .client
+            .send_checked("files.upload_start", request)
             .await?;
 
-        if !response.status().is_success() {
-            return Err(GeminiError::from_response(response, None).await);
-        }
-
         let upload_url = response
             .headers()
             .get("X-Goog-Upload-URL")
@@ -631,21 +2419,18 @@ impl<'a> FilesClient<'a> {
             }))
         })?;
 
-        let response = self
+        // The upload URL is already authorized for this upload.
+        let request = self
             .client
             .http_client
             .post(upload_url)
             .header("X-Goog-Upload-Command", "upload, finalize")
             .header("X-Goog-Upload-Offset", 0)
-            .body(file)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(GeminiError::from_response(response, None).await);
-        }
-
-        Ok(response.json().await?)
+            .body(file);
+        self.client
+            .send_parsed::<types::UploadFileResponse>("files.upload", request)
+            .await
+            .map(|response| response.file)
     }
 }
 
@@ -654,7 +2439,6 @@ impl<'a> FilesClient<'a> {
 /// # use gemini_client_rs::gemini_role;
 /// let role = gemini_role!(user);
 /// ```
-
 #[macro_export]
 macro_rules! gemini_role {
     (user) => {
@@ -724,7 +2508,6 @@ macro_rules! gemini_chat {
 ///     image("path/to/image.png")
 /// ];
 /// ```
-
 #[macro_export]
 macro_rules! gemini_parts {
     ($( $cmd:ident($arg:expr) ),* $(,)?) => {
@@ -736,8 +2519,6 @@ macro_rules! gemini_parts {
     };
 }
 
-
-
 #[doc(hidden)]
 #[macro_export]
 macro_rules! gemini_part_internal {
@@ -760,10 +2541,18 @@ macro_rules! gemini_part_internal {
         $crate::types::Part::thought($t)
     };
     (thought_signature($s:expr)) => {
-        $crate::types::Part::ThoughtSignature { signature: $s.to_string() }
+        $crate::types::Part::ThoughtSignature {
+            signature: $s.to_string(),
+        }
     };
 }
 
+/// Dependencies referenced by code generated with [`gemini_tool`].
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json;
+}
+
 #[doc(hidden)]
 pub fn base64_encode(data: &[u8]) -> String {
     use base64::{engine::general_purpose, Engine as _};
@@ -777,3 +2566,1505 @@ pub fn get_mime_type(path: &std::path::Path) -> String {
         .unwrap_or("application/octet-stream")
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read as _, Write as _};
+
+    use futures_util::StreamExt as _;
+    use serde_json::json;
+
+    use super::{model_resource, ApiVersion, Authorize as _, FunctionCallingEvent, GeminiClient};
+    use crate::transport::MockTransport;
+    use crate::types::{Content, GenerateContentRequest, GenerateContentResponse, Part, Role};
+    use crate::GeminiError;
+
+    #[test]
+    fn model_resource_keeps_collection_prefixes() {
+        assert_eq!(
+            model_resource("gemini-2.5-flash"),
+            "models/gemini-2.5-flash"
+        );
+        assert_eq!(
+            model_resource("models/gemini-2.5-flash"),
+            "models/gemini-2.5-flash"
+        );
+        assert_eq!(
+            model_resource("tunedModels/my-model-123"),
+            "tunedModels/my-model-123"
+        );
+    }
+
+    #[test]
+    fn api_version_changes_path_segment() {
+        let client = GeminiClient::new("key".to_string())
+            .with_base_url("http://localhost:8080".to_string())
+            .with_api_version(ApiVersion::V1);
+
+        assert_eq!(client.api_url(), "http://localhost:8080/v1");
+        assert_eq!(client.upload_url(), "http://localhost:8080/upload/v1/files");
+
+        let client = client.with_api_url("http://localhost:9090/custom".to_string());
+        assert_eq!(client.api_url(), "http://localhost:9090/custom");
+        assert_eq!(
+            client.upload_url(),
+            "http://localhost:9090/upload/custom/files"
+        );
+    }
+
+    #[test]
+    fn config_reports_settings_without_credentials() {
+        let client = GeminiClient::new("secret-key".to_string())
+            .with_base_url("http://localhost:8080".to_string())
+            .with_api_version(ApiVersion::V1)
+            .with_stream_retries(2)
+            .with_timeout(std::time::Duration::from_secs(30));
+        let config = client.config();
+
+        assert_eq!(config.base_url, "http://localhost:8080");
+        assert_eq!(config.api_version, ApiVersion::V1);
+        assert_eq!(config.api_url, None);
+        assert_eq!(config.stream_retries, 2);
+        assert_eq!(config.timeout, Some(std::time::Duration::from_secs(30)));
+        assert_eq!(config, client.clone().config());
+        assert!(!format!("{config:?}").contains("secret-key"));
+    }
+
+    #[tokio::test]
+    async fn requests_time_out() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        // Accepts the connection but never responds.
+        let server = std::thread::spawn(move || listener.accept().expect("accept connection"));
+
+        let client = GeminiClient::builder()
+            .api_key("key")
+            .api_url(format!("http://{address}/v1beta"))
+            .timeout(std::time::Duration::from_millis(100))
+            .build()
+            .expect("client should build");
+        assert_eq!(
+            client.config().timeout,
+            Some(std::time::Duration::from_millis(100))
+        );
+
+        let result = client
+            .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await;
+        assert!(matches!(result, Err(GeminiError::Timeout)), "{result:?}");
+        drop(server.join().expect("server thread"));
+    }
+
+    #[test]
+    fn debug_redacts_credentials() {
+        let client = GeminiClient::new("secret-key".to_string());
+        let output = format!("{client:?}");
+        assert!(output.contains(r#"api_key: "***""#));
+        assert!(!output.contains("secret-key"));
+
+        let client = client.with_bearer_token("secret-token");
+        assert!(!format!("{client:?}").contains("secret-token"));
+
+        let builder = super::GeminiClientBuilder::default().api_key("secret-key");
+        assert!(!format!("{builder:?}").contains("secret-key"));
+    }
+
+    #[tokio::test]
+    async fn connection_errors_redact_the_api_key() {
+        // Nothing listens on the port once the listener is dropped.
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("bind listener");
+        let client = GeminiClient::new("secret-key".to_string())
+            .with_api_url(format!("http://{address}/v1beta"));
+
+        let mut errors = vec![
+            client
+                .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+                .await
+                .expect_err("connection should be refused"),
+            client
+                .get_model("gemini-2.5-flash")
+                .await
+                .expect_err("connection should be refused"),
+        ];
+        let mut stream = client
+            .stream_generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect("stream should start");
+        errors.push(
+            stream
+                .next()
+                .await
+                .expect("stream should yield an error")
+                .expect_err("connection should be refused"),
+        );
+
+        for error in errors {
+            assert!(matches!(error, GeminiError::Http(_)), "{error:?}");
+            assert!(!format!("{error}").contains("secret-key"), "{error}");
+            assert!(!format!("{error:?}").contains("secret-key"), "{error:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_closes_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().expect("accept connection");
+            socket
+                .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+                .expect("set read timeout");
+            let mut buffer = [0u8; 8192];
+            let _ = socket.read(&mut buffer).expect("read request");
+
+            let event =
+                "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}}]}\n\n";
+            write!(
+                socket,
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{event}\r\n",
+                event.len()
+            )
+            .expect("write first event");
+            socket.flush().expect("flush first event");
+
+            // The response is never finished, so the only way this read
+            // returns is the client closing the connection.
+            loop {
+                match socket.read(&mut buffer) {
+                    Ok(0) => return true,
+                    Ok(_) => continue,
+                    Err(_) => return false,
+                }
+            }
+        });
+
+        let client =
+            GeminiClient::new("key".to_string()).with_api_url(format!("http://{address}/v1beta"));
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(Role::User, vec![Part::text("Hello")])],
+            ..Default::default()
+        };
+        let mut stream = client
+            .stream_generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("stream should start");
+        let first = stream
+            .next()
+            .await
+            .expect("stream should yield")
+            .expect("first chunk should parse");
+        assert_eq!(first.candidates.len(), 1);
+        drop(stream);
+
+        let closed = tokio::task::spawn_blocking(move || server.join().expect("server thread"))
+            .await
+            .expect("join server");
+        assert!(
+            closed,
+            "connection should be closed after dropping the stream"
+        );
+    }
+
+    /// Reads an HTTP request from `socket`, returning its JSON body.
+    fn read_json_request(socket: &mut std::net::TcpStream) -> serde_json::Value {
+        let mut received = vec![];
+        let mut buffer = [0u8; 8192];
+        let body_start = loop {
+            let read = socket.read(&mut buffer).expect("read request");
+            received.extend_from_slice(&buffer[..read]);
+            if let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&received[..body_start]).to_lowercase();
+        let length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|length| length.trim().parse().ok())
+            .unwrap_or_default();
+        while received.len() < body_start + length {
+            let read = socket.read(&mut buffer).expect("read request body");
+            received.extend_from_slice(&buffer[..read]);
+        }
+        serde_json::from_slice(&received[body_start..]).expect("request body is JSON")
+    }
+
+    /// Serves one `text/event-stream` response per connection, returning the
+    /// request bodies received.
+    fn serve_events(
+        responses: Vec<Vec<serde_json::Value>>,
+    ) -> (String, std::thread::JoinHandle<Vec<serde_json::Value>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = std::thread::spawn(move || {
+            let mut requests = vec![];
+            for events in responses {
+                let (mut socket, _) = listener.accept().expect("accept connection");
+                requests.push(read_json_request(&mut socket));
+
+                let body: String = events
+                    .iter()
+                    .map(|event| format!("data: {event}\n\n"))
+                    .collect();
+                write!(
+                    socket,
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .expect("write response");
+            }
+            requests
+        });
+        (format!("http://{address}/v1beta"), server)
+    }
+
+    #[tokio::test]
+    async fn oversized_responses_are_rejected() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let large = format!(r#"{{"candidates": [], "padding": "{}"}}"#, "x".repeat(4096));
+        let small = r#"{"candidates": [{"content": {"parts": [{"text": "Hi"}]}}]}"#;
+        let server = std::thread::spawn(move || {
+            let responses = [
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{large}",
+                    large.len()
+                ),
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{large}\r\n0\r\n\r\n",
+                    large.len()
+                ),
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{small}",
+                    small.len()
+                ),
+            ];
+            for response in responses {
+                let (mut socket, _) = listener.accept().expect("accept connection");
+                read_json_request(&mut socket);
+                // The client may hang up as soon as the limit is exceeded.
+                let _ = socket.write_all(response.as_bytes());
+            }
+        });
+
+        let client = GeminiClient::builder()
+            .api_key("key")
+            .api_url(format!("http://{address}/v1beta"))
+            .max_response_bytes(1024)
+            .pool_max_idle_per_host(0)
+            .build()
+            .expect("client should build");
+        let request = GenerateContentRequest::default();
+        for _ in 0..2 {
+            assert!(matches!(
+                client.generate_content("gemini-2.5-flash", &request).await,
+                Err(GeminiError::ResponseTooLarge { limit: 1024 })
+            ));
+        }
+        let response = client
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("small response should be accepted");
+        assert_eq!(response.candidates.len(), 1);
+        server.join().expect("server thread");
+    }
+
+    #[tokio::test]
+    async fn oversized_streams_are_cut_off() {
+        let small = json!({ "candidates": [{ "content": { "parts": [{ "text": "Hi" }] } }] });
+        let large = json!({ "candidates": [], "padding": "x".repeat(4096) });
+        let (api_url, server) = serve_events(vec![vec![large], vec![small; 40]]);
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url(api_url)
+            .with_max_response_bytes(1024)
+            .with_stream_retries(1);
+        let request = GenerateContentRequest::default();
+
+        // A single oversized event fails without being retried.
+        let results: Vec<_> = client
+            .stream_generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("stream should start")
+            .collect()
+            .await;
+        assert!(
+            matches!(
+                results.as_slice(),
+                [Err(GeminiError::ResponseTooLarge { limit: 1024 })]
+            ),
+            "{results:?}"
+        );
+
+        // Small events count towards the limit too.
+        let results: Vec<_> = client
+            .stream_generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("stream should start")
+            .collect()
+            .await;
+        let (last, chunks) = results.split_last().expect("stream should yield");
+        assert!(chunks.len() < 40);
+        assert!(chunks.iter().all(Result::is_ok));
+        assert!(matches!(last, Err(GeminiError::ResponseTooLarge { .. })));
+        assert_eq!(server.join().expect("server thread").len(), 2);
+    }
+
+    /// Serves one connection per entry of `first_events`, writing those
+    /// events and then dropping the connection mid-response.
+    fn serve_truncated_events(
+        first_events: Vec<Vec<&'static str>>,
+        listener: std::net::TcpListener,
+    ) -> std::thread::JoinHandle<usize> {
+        std::thread::spawn(move || {
+            let mut connections = 0;
+            for events in first_events {
+                let (mut socket, _) = listener.accept().expect("accept connection");
+                connections += 1;
+                read_json_request(&mut socket);
+                write!(
+                    socket,
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n"
+                )
+                .expect("write headers");
+                for event in events {
+                    let event = format!("data: {event}\n\n");
+                    write!(socket, "{:x}\r\n{event}\r\n", event.len()).expect("write event");
+                }
+                socket.flush().expect("flush events");
+            }
+            connections
+        })
+    }
+
+    #[tokio::test]
+    async fn stream_retries_only_before_the_first_chunk() {
+        let chunk = r#"{"candidates": [{"content": {"parts": [{"text": "Hi"}]}}]}"#;
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(Role::User, vec![Part::text("Hello")])],
+            ..Default::default()
+        };
+
+        // Fails before any chunk, then succeeds after a retry.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = serve_truncated_events(vec![vec![], vec![chunk]], listener);
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url(format!("http://{address}/v1beta"))
+            .with_stream_retries(1);
+        let mut stream = client
+            .stream_generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("stream should start");
+        let first = stream.next().await.expect("retried stream yields a chunk");
+        assert_eq!(first.expect("chunk should parse").candidates.len(), 1);
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(GeminiError::Http(_)))
+        ));
+        assert!(stream.next().await.is_none());
+        assert_eq!(server.join().expect("server thread"), 2);
+
+        // Fails after a chunk, which is surfaced rather than retried.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = serve_truncated_events(vec![vec![chunk]], listener);
+        let client = client.with_api_url(format!("http://{address}/v1beta"));
+        let mut stream = client
+            .stream_generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("stream should start");
+        assert!(matches!(stream.next().await, Some(Ok(_))));
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(GeminiError::Http(_)))
+        ));
+        assert!(stream.next().await.is_none());
+        assert_eq!(server.join().expect("server thread"), 1);
+    }
+
+    #[tokio::test]
+    async fn function_calling_stream_stops_after_max_turns() {
+        let (client, transport) = mock_client();
+        let call = json!({
+            "candidates": [{ "content": { "role": "model", "parts": [
+                { "functionCall": { "name": "lookup", "args": {} } }
+            ] } }]
+        });
+        transport.push_events(&[&call]);
+        transport.push_events(&[&call]);
+
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let events: Vec<_> = client
+            .stream_generate_content_with_function_calling(
+                "gemini-2.5-flash",
+                GenerateContentRequest::default(),
+                2,
+                move |_| {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    std::future::ready(Ok(json!({})))
+                },
+            )
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert!(matches!(
+            events[2],
+            Err(GeminiError::TooManyTurns { max_turns: 2 })
+        ));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn function_calling_stream_runs_tools_then_streams_answer() {
+        let (api_url, server) = serve_events(vec![
+            vec![json!({
+                "candidates": [{ "content": { "role": "model", "parts": [
+                    { "functionCall": { "id": "1", "name": "get_weather", "args": { "location": "Paris" } } }
+                ] } }]
+            })],
+            vec![
+                json!({ "candidates": [{ "content": { "role": "model", "parts": [{ "text": "It is " }] } }] }),
+                json!({ "candidates": [{ "content": { "role": "model", "parts": [{ "text": "sunny." }] }, "finishReason": "STOP" }] }),
+            ],
+        ]);
+
+        let client = GeminiClient::new("key".to_string()).with_api_url(api_url);
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(
+                Role::User,
+                vec![Part::text("Weather in Paris?")],
+            )],
+            ..Default::default()
+        };
+        let events: Vec<FunctionCallingEvent> = client
+            .stream_generate_content_with_function_calling(
+                "gemini-2.5-flash",
+                request,
+                5,
+                |call| async move {
+                    assert_eq!(call.name, "get_weather");
+                    // Stands in for I/O, which must not block the stream.
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    Ok(json!({ "forecast": "sunny" }))
+                },
+            )
+            .map(|event| event.expect("event should succeed"))
+            .collect()
+            .await;
+
+        assert!(
+            matches!(&events[0], FunctionCallingEvent::ToolCall(call) if call.name == "get_weather")
+        );
+        assert!(
+            matches!(&events[1], FunctionCallingEvent::ToolResult(response) if response.id.as_deref() == Some("1"))
+        );
+        assert_eq!(
+            events[2..],
+            [
+                FunctionCallingEvent::TextDelta("It is ".to_string()),
+                FunctionCallingEvent::TextDelta("sunny.".to_string()),
+            ]
+        );
+
+        let requests = server.join().expect("server thread");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1]["contents"][1],
+            json!({ "role": "model", "parts": [
+                { "functionCall": { "id": "1", "name": "get_weather", "args": { "location": "Paris" } } }
+            ] })
+        );
+        assert_eq!(requests[1]["contents"][2]["role"], "user");
+        assert_eq!(
+            requests[1]["contents"][2]["parts"][0]["functionResponse"]["response"],
+            json!({ "content": { "forecast": "sunny" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_transport_serves_queued_responses() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hi!" }] } }]
+        }));
+        transport.push_response(
+            429,
+            r#"{"error": {"code": 429, "status": "RESOURCE_EXHAUSTED"}}"#,
+        );
+
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url("http://mock/v1beta".to_string())
+            .with_transport(transport.clone());
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(Role::User, vec![Part::text("Hello")])],
+            ..Default::default()
+        };
+
+        let response = client
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("first response should succeed");
+        assert_eq!(response.candidates.len(), 1);
+
+        let error = client
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect_err("second response should fail");
+        assert!(matches!(error, GeminiError::RateLimited { .. }));
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].url,
+            "http://mock/v1beta/models/gemini-2.5-flash:generateContent?key=key"
+        );
+        assert_eq!(requests[0].body["contents"][0]["parts"][0]["text"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn every_endpoint_goes_through_the_transport() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url("http://mock/v1beta".to_string())
+            .with_transport(transport.clone());
+
+        transport.push_json(&serde_json::json!({ "models": [] }));
+        assert!(client
+            .list_models()
+            .await
+            .expect("models should list")
+            .is_empty());
+        transport.push_json(&serde_json::json!({ "model": "models/gemini-2.5-flash" }));
+        client
+            .caches()
+            .update_ttl("cachedContents/abc", std::time::Duration::from_secs(60))
+            .await
+            .expect("cache should update");
+        transport.push_json(&serde_json::json!({}));
+        client
+            .caches()
+            .delete("cachedContents/abc")
+            .await
+            .expect("cache should delete");
+        transport.push_events(&[serde_json::json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hi" }] } }]
+        })]);
+        let chunks: Vec<_> = client
+            .stream_generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect("stream should start")
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_ok(), "{chunks:?}");
+
+        let requests: Vec<_> = transport
+            .requests()
+            .into_iter()
+            .map(|request| (request.method.to_string(), request.url))
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                ("GET", "http://mock/v1beta/models?key=key&pageSize=1000"),
+                (
+                    "PATCH",
+                    "http://mock/v1beta/cachedContents/abc?key=key&updateMask=ttl"
+                ),
+                ("DELETE", "http://mock/v1beta/cachedContents/abc?key=key"),
+                (
+                    "POST",
+                    "http://mock/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse&key=key"
+                ),
+            ]
+            .map(|(method, url)| (method.to_string(), url.to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn transport_requests_are_authorized_like_direct_ones() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("a&b=c".to_string())
+            .with_api_url("http://mock/v1beta".to_string())
+            .with_transport(transport.clone());
+        let url = "http://mock/v1beta/models/gemini-2.5-flash:generateContent";
+        let direct = client
+            .http_client
+            .post(url)
+            .authorize(&client)
+            .build()
+            .expect("request should build");
+        assert_eq!(direct.url().query(), Some("key=a%26b%3Dc"));
+
+        transport.push_json(&serde_json::json!({ "candidates": [] }));
+        client
+            .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect("response should parse");
+        assert_eq!(transport.requests()[0].url, direct.url().as_str());
+    }
+
+    #[tokio::test]
+    async fn bearer_tokens_refresh_across_clones() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url("http://mock/v1beta".to_string())
+            .with_transport(transport.clone())
+            .with_bearer_token("first");
+        let shared = client.clone();
+        let request = GenerateContentRequest::default();
+
+        for _ in 0..2 {
+            transport.push_json(&serde_json::json!({ "candidates": [] }));
+        }
+        shared
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("response should parse");
+        client.set_bearer_token("second");
+        shared
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("response should parse");
+
+        let requests = transport.requests();
+        assert_eq!(
+            requests[0].headers[reqwest::header::AUTHORIZATION],
+            "Bearer first"
+        );
+        assert_eq!(
+            requests[1].headers[reqwest::header::AUTHORIZATION],
+            "Bearer second"
+        );
+        assert!(requests.iter().all(|request| !request.url.contains("key=")));
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_rejected_api_key() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().expect("accept connection");
+            let mut buffer = [0u8; 8192];
+            let _ = socket.read(&mut buffer).expect("read request");
+            let body = r#"{"error": {"code": 400, "status": "INVALID_ARGUMENT", "details": [{"reason": "API_KEY_INVALID"}]}}"#;
+            write!(
+                socket,
+                "HTTP/1.1 400 Bad Request\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .expect("write response");
+        });
+
+        let client = GeminiClient::new("bad-key".to_string())
+            .with_api_url(format!("http://{address}/v1beta"));
+        let error = client
+            .health_check()
+            .await
+            .expect_err("key should be rejected");
+        assert!(
+            matches!(error, GeminiError::InvalidApiKey(ref value) if value["error"]["code"] == 400)
+        );
+        server.join().expect("server thread");
+    }
+
+    #[tokio::test]
+    async fn generate_with_cache_sends_only_new_turns() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&json!({ "candidates": [] }));
+        let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+
+        client
+            .generate_with_cache(
+                "gemini-2.5-flash",
+                "cachedContents/abc123",
+                vec![Content::multimodal(
+                    Role::User,
+                    vec![Part::text("Next question")],
+                )],
+            )
+            .await
+            .expect("response should parse");
+
+        assert_eq!(
+            transport.requests()[0].body,
+            json!({
+                "contents": [{ "role": "user", "parts": [{ "text": "Next question" }] }],
+                "cachedContent": "cachedContents/abc123"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn generate_content_raw_passes_json_through() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&json!({ "candidates": [], "newField": { "enabled": true } }));
+        let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+
+        let body = json!({
+            "contents": [{ "parts": [{ "text": "Hi" }] }],
+            "unmodeledConfig": { "mode": "EXPERIMENTAL" }
+        });
+        let response = client
+            .generate_content_raw("gemini-2.5-flash", body.clone())
+            .await
+            .expect("response should be JSON");
+
+        assert_eq!(response["newField"]["enabled"], true);
+        assert_eq!(transport.requests()[0].body, body);
+    }
+
+    #[tokio::test]
+    async fn malformed_responses_keep_the_raw_body() {
+        let body = r#"{"candidates": "not a list"}"#;
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_response(200, body);
+        let client = GeminiClient::new("key".to_string()).with_transport(transport);
+
+        let error = client
+            .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect_err("response should not parse");
+        assert!(matches!(error, GeminiError::Json { ref data, .. } if data == body));
+        assert!(error.to_string().contains(body), "{error}");
+
+        let (api_url, server) = serve_events(vec![vec![json!({ "candidates": 1 })]]);
+        let client = GeminiClient::new("key".to_string()).with_api_url(api_url);
+        let mut stream = client
+            .stream_generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect("stream should start");
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(GeminiError::Json { data, .. })) if data == r#"{"candidates":1}"#
+        ));
+        drop(stream);
+        server.join().expect("server thread");
+    }
+
+    #[tokio::test]
+    async fn invalid_requests_are_rejected_on_every_path() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url("http://127.0.0.1:9/v1beta".to_string())
+            .with_transport(transport.clone());
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(
+                Role::User,
+                vec![Part::inline_data("image/png", "not base64!")],
+            )],
+            ..Default::default()
+        };
+        let model = "gemini-2.5-flash";
+
+        assert!(matches!(
+            client.generate_content(model, &request).await,
+            Err(GeminiError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            client.count_tokens(model, &request).await,
+            Err(GeminiError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            client.stream_generate_content(model, &request).await,
+            Err(GeminiError::InvalidRequest(_))
+        ));
+        let mut events =
+            client.stream_generate_content_with_function_calling(model, request, 5, |_| {
+                std::future::ready(Ok(json!({})))
+            });
+        assert!(matches!(
+            events.next().await,
+            Some(Err(GeminiError::InvalidRequest(_)))
+        ));
+        assert!(transport.requests().is_empty(), "nothing should be sent");
+    }
+
+    /// A transport whose requests never complete.
+    #[derive(Debug)]
+    struct PendingTransport;
+
+    #[async_trait::async_trait]
+    impl crate::Transport for PendingTransport {
+        async fn send(&self, _request: reqwest::Request) -> Result<reqwest::Response, GeminiError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_deadlines_time_out() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+        let request = GenerateContentRequest::default();
+
+        let past = std::time::Instant::now() - std::time::Duration::from_secs(1);
+        assert!(matches!(
+            client
+                .generate_content_with_deadline("gemini-2.5-flash", &request, past)
+                .await,
+            Err(GeminiError::Timeout)
+        ));
+        assert!(transport.requests().is_empty(), "nothing should be sent");
+
+        let client = client.with_transport(std::sync::Arc::new(PendingTransport));
+        let soon = std::time::Instant::now() + std::time::Duration::from_millis(50);
+        assert!(matches!(
+            client
+                .generate_content_with_deadline("gemini-2.5-flash", &request, soon)
+                .await,
+            Err(GeminiError::Timeout)
+        ));
+    }
+
+    #[tokio::test]
+    async fn requests_identify_the_api_client() {
+        let client = GeminiClient::new("key".to_string());
+        let request = client
+            .http_client
+            .get("http://localhost/v1beta/models")
+            .authorize(&client)
+            .build()
+            .expect("request should build");
+        assert_eq!(
+            request.headers()["x-goog-api-client"],
+            GeminiClient::DEFAULT_API_CLIENT
+        );
+        assert!(GeminiClient::DEFAULT_API_CLIENT.starts_with("gemini-client-rs/"));
+
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&serde_json::json!({ "candidates": [] }));
+        let client = client
+            .with_api_client("my-app/1.2.3")
+            .with_transport(transport.clone());
+        client
+            .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+            .await
+            .expect("response should parse");
+        assert_eq!(
+            transport.requests()[0].headers["x-goog-api-client"],
+            "my-app/1.2.3"
+        );
+    }
+
+    /// Answers every request with `body` on keep-alive connections,
+    /// returning the number of connections accepted so far.
+    fn serve_json_keep_alive(
+        body: serde_json::Value,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::BufRead;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let accepted = connections.clone();
+        std::thread::spawn(move || {
+            for socket in listener.incoming() {
+                let mut socket = socket.expect("accept connection");
+                accepted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let body = body.to_string();
+                std::thread::spawn(move || {
+                    let mut reader =
+                        std::io::BufReader::new(socket.try_clone().expect("clone socket"));
+                    loop {
+                        let mut length = 0;
+                        let mut line = String::new();
+                        while reader.read_line(&mut line).unwrap_or_default() > 0 {
+                            if line == "\r\n" {
+                                break;
+                            }
+                            if let Some(value) = line.to_lowercase().strip_prefix("content-length:")
+                            {
+                                length = value.trim().parse().expect("content length");
+                            }
+                            line.clear();
+                        }
+                        if line.is_empty() {
+                            // The client closed the connection.
+                            return;
+                        }
+                        let mut request = vec![0; length];
+                        reader.read_exact(&mut request).expect("read request body");
+                        write!(
+                            socket,
+                            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .expect("write response");
+                    }
+                });
+            }
+        });
+        (format!("http://{address}/v1beta"), connections)
+    }
+
+    #[tokio::test]
+    async fn builder_configures_connection_pool() {
+        for (max_idle, expected_connections) in [(None, 1), (Some(0), 2)] {
+            let (url, connections) = serve_json_keep_alive(json!({ "candidates": [] }));
+            let mut builder = GeminiClient::builder()
+                .api_key("key")
+                .api_url(url)
+                .pool_idle_timeout(std::time::Duration::from_secs(30));
+            if let Some(max) = max_idle {
+                builder = builder.pool_max_idle_per_host(max);
+            }
+            let client = builder.build().expect("client should build");
+
+            for _ in 0..2 {
+                client
+                    .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+                    .await
+                    .expect("request should succeed");
+            }
+            assert_eq!(
+                connections.load(std::sync::atomic::Ordering::SeqCst),
+                expected_connections,
+                "pool_max_idle_per_host({max_idle:?})"
+            );
+        }
+    }
+
+    #[test]
+    fn auth_errors_are_detected_by_status_and_reason() {
+        let forbidden =
+            GeminiError::from_status(403, r#"{"error": {"code": 403}}"#.to_string(), None);
+        assert!(forbidden.is_auth_error());
+
+        let invalid_key = GeminiError::from_status(
+            400,
+            r#"{"error": {"code": 400, "details": [{"reason": "API_KEY_INVALID"}]}}"#.to_string(),
+            None,
+        );
+        assert!(invalid_key.is_auth_error());
+
+        let bad_request = GeminiError::from_status(400, "Bad Request".to_string(), None);
+        assert!(!bad_request.is_auth_error());
+        assert!(!GeminiError::Timeout.is_auth_error());
+    }
+
+    #[test]
+    fn rate_limit_errors_carry_quota_details() {
+        let body = json!({
+            "error": {
+                "code": 429,
+                "message": "You exceeded your current quota.",
+                "status": "RESOURCE_EXHAUSTED",
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.rpc.QuotaFailure",
+                        "violations": [{
+                            "quotaMetric": "generativelanguage.googleapis.com/generate_content_free_tier_requests",
+                            "quotaId": "GenerateRequestsPerDayPerProjectPerModel-FreeTier",
+                            "quotaDimensions": { "location": "global", "model": "gemini-2.0-flash" },
+                            "quotaValue": "200"
+                        }]
+                    },
+                    { "@type": "type.googleapis.com/google.rpc.Help", "links": [] },
+                    { "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "39s" }
+                ]
+            }
+        });
+
+        let error = GeminiError::from_status(429, body.to_string(), None);
+        let GeminiError::RateLimited {
+            message,
+            retry_after,
+            violations,
+        } = error
+        else {
+            panic!("expected a rate limit error, got {error:?}");
+        };
+        assert_eq!(message, "You exceeded your current quota.");
+        assert_eq!(retry_after, Some(std::time::Duration::from_secs(39)));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].is_per_day());
+        assert!(!violations[0].is_per_minute());
+        assert_eq!(violations[0].quota_dimensions["model"], "gemini-2.0-flash");
+
+        let bare = GeminiError::from_status(429, "Too Many Requests".to_string(), None);
+        assert!(matches!(
+            bare,
+            GeminiError::RateLimited { retry_after: None, ref violations, .. } if violations.is_empty()
+        ));
+    }
+
+    #[derive(Debug)]
+    struct Watermark(&'static str);
+
+    impl crate::Interceptor for Watermark {
+        fn before(&self, request: &mut std::borrow::Cow<'_, GenerateContentRequest>) {
+            request.to_mut().contents[0].parts.push(Part::text(self.0));
+        }
+
+        fn after(&self, response: &mut GenerateContentResponse) {
+            response.response_id = Some(format!(
+                "{}{}",
+                response.response_id.take().unwrap_or_default(),
+                self.0
+            ));
+        }
+    }
+
+    #[derive(Debug)]
+    struct Observer(std::sync::atomic::AtomicUsize);
+
+    impl crate::Interceptor for Observer {
+        fn before(&self, request: &mut std::borrow::Cow<'_, GenerateContentRequest>) {
+            self.0
+                .fetch_add(request.contents.len(), std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn observing_interceptors_do_not_copy_the_request() {
+        let observer = std::sync::Arc::new(Observer(Default::default()));
+        let client = GeminiClient::new("key".to_string()).with_interceptor(observer.clone());
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(Role::User, vec![Part::text("Hello")])],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            client.intercept_request(&request),
+            std::borrow::Cow::Borrowed(_)
+        ));
+        assert_eq!(observer.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let client = client.with_interceptor(std::sync::Arc::new(Watermark("-a")));
+        assert!(matches!(
+            client.intercept_request(&request),
+            std::borrow::Cow::Owned(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn interceptors_run_in_registration_order() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&json!({ "candidates": [], "responseId": "id" }));
+
+        let client = GeminiClient::new("key".to_string())
+            .with_transport(transport.clone())
+            .with_interceptor(std::sync::Arc::new(Watermark("-a")))
+            .with_interceptor(std::sync::Arc::new(Watermark("-b")));
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(Role::User, vec![Part::text("Hello")])],
+            ..Default::default()
+        };
+
+        let response = client
+            .generate_content("gemini-2.5-flash", &request)
+            .await
+            .expect("response should succeed");
+        assert_eq!(response.response_id(), Some("id-a-b"));
+        assert_eq!(
+            transport.requests()[0].body["contents"][0]["parts"],
+            json!([{ "text": "Hello" }, { "text": "-a" }, { "text": "-b" }])
+        );
+        assert_eq!(request.contents[0].parts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn embed_corpus_batches_texts_in_order() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        for batch in [[1.0, 2.0].as_slice(), &[3.0, 4.0], &[5.0]] {
+            let embeddings: Vec<_> = batch
+                .iter()
+                .map(|value| json!({ "values": [value] }))
+                .collect();
+            transport.push_json(&json!({ "embeddings": embeddings }));
+        }
+
+        let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+        let texts = futures_util::stream::iter((1..=5).map(|index| format!("text {index}")));
+        let values: Vec<f32> = client
+            .embed_corpus("text-embedding-004", texts, 2, 2)
+            .map(|embedding| embedding.expect("embedding should succeed").values[0])
+            .collect()
+            .await;
+
+        assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0]);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[0].body["requests"][1]["model"],
+            "models/text-embedding-004"
+        );
+        assert_eq!(
+            requests[2].body["requests"][0]["content"]["parts"][0]["text"],
+            "text 5"
+        );
+    }
+
+    #[tokio::test]
+    async fn embed_corpus_fails_every_text_of_a_failed_batch() {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        transport.push_json(&json!({ "embeddings": [{ "values": [1.0] }, { "values": [2.0] }] }));
+        transport.push_response(500, r#"{"error": {"code": 500, "message": "internal"}}"#);
+        transport.push_json(&json!({ "embeddings": [{ "values": [5.0] }, { "values": [6.0] }] }));
+
+        let client = GeminiClient::new("key".to_string()).with_transport(transport);
+        let texts = futures_util::stream::iter((1..=5).map(|index| format!("text {index}")));
+        let results: Vec<_> = client
+            .embed_corpus("text-embedding-004", texts, 2, 1)
+            .collect()
+            .await;
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[1].as_ref().expect("embedding").values, [2.0]);
+        for (index, result) in results.iter().enumerate().skip(2) {
+            let Err(GeminiError::EmbeddingBatchFailed {
+                index: failed,
+                source,
+            }) = result
+            else {
+                panic!("text {index} should fail, got {result:?}");
+            };
+            assert_eq!(*failed, index);
+            if index < 4 {
+                assert!(matches!(**source, GeminiError::Api(_)), "{source:?}");
+            } else {
+                assert!(matches!(
+                    **source,
+                    GeminiError::EmbeddingCountMismatch {
+                        expected: 1,
+                        actual: 2
+                    }
+                ));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn bearer_token_replaces_api_key() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("listener address");
+        let server = std::thread::spawn(move || {
+            let (mut socket, _) = listener.accept().expect("accept connection");
+            let mut buffer = [0u8; 8192];
+            let read = socket.read(&mut buffer).expect("read request");
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let body = r#"{"name": "models/gemini-2.5-flash", "version": "001", "displayName": "Gemini", "inputTokenLimit": 1, "outputTokenLimit": 1, "supportedGenerationMethods": []}"#;
+            write!(
+                socket,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .expect("write response");
+            request
+        });
+
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url(format!("http://{address}/v1beta"))
+            .with_bearer_token("token");
+        let model = client
+            .get_model("gemini-2.5-flash")
+            .await
+            .expect("model should be fetched");
+        assert_eq!(model.base_model_id, "gemini-2.5-flash");
+
+        let request = server.join().expect("server thread");
+        assert!(
+            request.starts_with("GET /v1beta/models/gemini-2.5-flash HTTP/1.1"),
+            "{request}"
+        );
+        assert!(
+            request
+                .to_lowercase()
+                .contains("authorization: bearer token"),
+            "{request}"
+        );
+    }
+
+    /// A client whose requests go to a fresh [`MockTransport`].
+    fn mock_client() -> (GeminiClient, std::sync::Arc<MockTransport>) {
+        let transport = std::sync::Arc::new(MockTransport::new());
+        let client = GeminiClient::new("key".to_string())
+            .with_api_url("http://mock/v1beta".to_string())
+            .with_transport(transport.clone());
+        (client, transport)
+    }
+
+    #[tokio::test]
+    async fn corpora_client_creates_lists_and_deletes() {
+        let (client, transport) = mock_client();
+        let corpora = client.corpora();
+
+        transport.push_json(&json!({ "name": "corpora/123", "displayName": "Docs" }));
+        let corpus = corpora
+            .create_corpus(&crate::types::Corpus {
+                display_name: Some("Docs".to_string()),
+                ..Default::default()
+            })
+            .await
+            .expect("corpus should be created");
+        assert_eq!(corpus.name.as_deref(), Some("corpora/123"));
+
+        transport.push_json(&json!({
+            "corpora": [{ "name": "corpora/123" }],
+            "nextPageToken": "page-2"
+        }));
+        transport.push_json(&json!({ "corpora": [{ "name": "corpora/456" }] }));
+        let names: Vec<_> = corpora
+            .list_corpora()
+            .await
+            .expect("corpora should be listed")
+            .into_iter()
+            .filter_map(|corpus| corpus.name)
+            .collect();
+        assert_eq!(names, ["corpora/123", "corpora/456"]);
+
+        transport.push_json(&json!({}));
+        corpora
+            .delete_corpus("corpora/123", true)
+            .await
+            .expect("corpus should be deleted");
+        transport.push_response(
+            400,
+            r#"{"error": {"code": 400, "message": "Corpus is not empty."}}"#,
+        );
+        let error = corpora
+            .delete_corpus("corpora/456", false)
+            .await
+            .expect_err("non-empty corpus should not be deleted");
+        assert!(matches!(&error, GeminiError::Api(body) if body["status"] == 400));
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].method, reqwest::Method::POST);
+        assert_eq!(requests[0].url, "http://mock/v1beta/corpora?key=key");
+        assert_eq!(requests[0].body, json!({ "displayName": "Docs" }));
+        assert_eq!(
+            requests[1].url,
+            "http://mock/v1beta/corpora?key=key&pageSize=20"
+        );
+        assert_eq!(
+            requests[2].url,
+            "http://mock/v1beta/corpora?key=key&pageSize=20&pageToken=page-2"
+        );
+        assert_eq!(requests[3].method, reqwest::Method::DELETE);
+        assert_eq!(
+            requests[3].url,
+            "http://mock/v1beta/corpora/123?key=key&force=true"
+        );
+        assert_eq!(requests[4].url, "http://mock/v1beta/corpora/456?key=key");
+    }
+
+    /// A `File` resource as returned by the Files API.
+    fn file_json(name: &str, state: &str, expiration_time: &str) -> serde_json::Value {
+        json!({
+            "name": name,
+            "mimeType": "video/mp4",
+            "sizeBytes": "1024",
+            "createTime": "2024-05-20T12:00:00Z",
+            "updateTime": "2024-05-20T12:00:00Z",
+            "expirationTime": expiration_time,
+            "sha256Hash": "abc",
+            "uri": format!("http://mock/v1beta/{name}"),
+            "state": state,
+        })
+    }
+
+    #[tokio::test]
+    async fn files_are_polled_until_active() {
+        let (client, transport) = mock_client();
+        let expires = "2024-05-22T12:00:00Z";
+        let options = crate::types::FilePollOptions {
+            poll_interval: std::time::Duration::from_millis(1),
+            timeout: std::time::Duration::from_secs(5),
+        };
+
+        let path = std::env::temp_dir().join(format!("gemini-poll-{}.mp4", std::process::id()));
+        std::fs::write(&path, b"video").expect("write temp file");
+        transport.push_json(&json!({ "file": file_json("files/abc", "PROCESSING", expires) }));
+        transport.push_json(&file_json("files/abc", "PROCESSING", expires));
+        transport.push_json(&file_json("files/abc", "ACTIVE", expires));
+        let file = client.files().upload_file_and_wait(&path, options).await;
+        std::fs::remove_file(&path).expect("remove temp file");
+        let file = file.expect("file should become active");
+        assert_eq!(file.state, crate::types::FileState::Active);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].method, reqwest::Method::POST);
+        assert_eq!(requests[0].url, "http://mock/upload/v1beta/files?key=key");
+        for request in &requests[1..] {
+            assert_eq!(request.method, reqwest::Method::GET);
+            assert_eq!(request.url, "http://mock/v1beta/files/abc?key=key");
+        }
+
+        transport.push_json(&file_json("files/def", "FAILED", expires));
+        let error = client
+            .files()
+            .wait_until_active("files/def", options)
+            .await
+            .expect_err("failed file should not become active");
+        assert!(matches!(&error, GeminiError::FileProcessingFailed(name) if name == "files/def"));
+
+        let options = crate::types::FilePollOptions {
+            poll_interval: std::time::Duration::from_secs(10),
+            timeout: std::time::Duration::from_secs(1),
+        };
+        transport.push_json(&file_json("files/ghi", "PROCESSING", expires));
+        let error = client
+            .files()
+            .wait_until_active("files/ghi", options)
+            .await
+            .expect_err("processing file should time out");
+        assert!(matches!(error, GeminiError::Timeout));
+
+        transport.push_response(404, r#"{"error": {"code": 404, "status": "NOT_FOUND"}}"#);
+        let error = client
+            .files()
+            .get_file("files/missing")
+            .await
+            .expect_err("missing file should fail");
+        assert!(matches!(&error, GeminiError::Api(body) if body["status"] == 404));
+    }
+
+    #[tokio::test]
+    async fn expired_files_are_listed_and_deleted() {
+        let (client, transport) = mock_client();
+        transport.push_json(&json!({
+            "files": [
+                file_json("files/expired", "ACTIVE", "2020-01-01T00:00:00Z"),
+                file_json("files/fresh", "ACTIVE", "2999-01-01T00:00:00.5+02:00"),
+            ],
+            "nextPageToken": "page-2"
+        }));
+        transport.push_json(&json!({
+            "files": [file_json("files/unknown", "ACTIVE", "soon")]
+        }));
+        transport.push_json(&json!({}));
+
+        let deleted = client
+            .files()
+            .delete_expired_files(std::time::Duration::from_secs(3600))
+            .await
+            .expect("expired files should be deleted");
+        assert_eq!(deleted, 1);
+
+        let requests: Vec<_> = transport
+            .requests()
+            .into_iter()
+            .map(|request| (request.method.to_string(), request.url))
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                ("GET", "http://mock/v1beta/files?key=key&pageSize=100"),
+                (
+                    "GET",
+                    "http://mock/v1beta/files?key=key&pageSize=100&pageToken=page-2"
+                ),
+                ("DELETE", "http://mock/v1beta/files/expired?key=key"),
+            ]
+            .map(|(method, url)| (method.to_string(), url.to_string()))
+        );
+
+        transport.push_response(403, r#"{"error": {"code": 403}}"#);
+        let error = client
+            .files()
+            .delete_file("files/other")
+            .await
+            .expect_err("forbidden delete should fail");
+        assert!(error.is_auth_error());
+    }
+
+    #[tokio::test]
+    async fn tuned_models_client_creates_gets_and_lists() {
+        use crate::types::{TunedModel, TuningDataset, TuningExample, TuningExamples, TuningTask};
+
+        let (client, transport) = mock_client();
+        let tuned_models = client.tuned_models();
+
+        transport.push_json(&json!({
+            "name": "tunedModels/abc/operations/xyz",
+            "metadata": { "completedPercent": 0 }
+        }));
+        let operation = tuned_models
+            .create_tuned_model(&TunedModel {
+                base_model: Some("models/gemini-1.5-flash-001-tuning".to_string()),
+                tuning_task: TuningTask {
+                    training_data: Some(TuningDataset {
+                        examples: TuningExamples {
+                            examples: vec![TuningExample {
+                                text_input: "1".to_string(),
+                                output: "2".to_string(),
+                            }],
+                        },
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .expect("tuning should start");
+        assert_eq!(operation.name, "tunedModels/abc/operations/xyz");
+        assert!(!operation.done);
+
+        transport.push_json(&json!({
+            "name": "tunedModels/abc/operations/xyz",
+            "done": true,
+            "response": { "name": "tunedModels/abc" }
+        }));
+        let operation = tuned_models
+            .get_tuning_operation("tunedModels/abc/operations/xyz")
+            .await
+            .expect("operation should be fetched");
+        assert!(operation.done);
+
+        transport.push_json(&json!({
+            "name": "tunedModels/abc",
+            "state": "ACTIVE",
+            "tuningTask": {}
+        }));
+        let tuned_model = tuned_models
+            .get_tuned_model("tunedModels/abc")
+            .await
+            .expect("tuned model should be fetched");
+        assert_eq!(
+            tuned_model.state,
+            Some(crate::types::TunedModelState::Active)
+        );
+
+        transport.push_json(&json!({
+            "tunedModels": [{ "name": "tunedModels/abc", "tuningTask": {} }],
+            "nextPageToken": "page-2"
+        }));
+        transport.push_json(&json!({
+            "tunedModels": [{ "name": "tunedModels/def", "tuningTask": {} }]
+        }));
+        let names: Vec<_> = tuned_models
+            .list_tuned_models()
+            .await
+            .expect("tuned models should be listed")
+            .into_iter()
+            .filter_map(|tuned_model| tuned_model.name)
+            .collect();
+        assert_eq!(names, ["tunedModels/abc", "tunedModels/def"]);
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].method, reqwest::Method::POST);
+        assert_eq!(requests[0].url, "http://mock/v1beta/tunedModels?key=key");
+        assert_eq!(
+            requests[0].body["tuningTask"]["trainingData"]["examples"]["examples"][0],
+            json!({ "textInput": "1", "output": "2" })
+        );
+        assert_eq!(
+            requests[1].url,
+            "http://mock/v1beta/tunedModels/abc/operations/xyz?key=key"
+        );
+        assert_eq!(
+            requests[2].url,
+            "http://mock/v1beta/tunedModels/abc?key=key"
+        );
+        assert_eq!(
+            requests[4].url,
+            "http://mock/v1beta/tunedModels?key=key&pageSize=1000&pageToken=page-2"
+        );
+    }
+}
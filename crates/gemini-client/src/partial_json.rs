@@ -0,0 +1,390 @@
+use serde_json::Value;
+
+use crate::types::{GenerateContentResponse, Part};
+use crate::GeminiError;
+
+/// Accumulates streamed JSON text and exposes a best-effort parse of the
+/// incomplete document so far.
+///
+/// Useful with `response_mime_type: application/json` to show progress
+/// while a structured response is still streaming in.
+///
+/// ```rust
+/// # use gemini_client_rs::StreamingJsonAccumulator;
+/// # use serde_json::json;
+/// let mut accumulator = StreamingJsonAccumulator::new();
+/// accumulator.push(r#"{"title": "Rust", "tags": ["fa"#);
+/// assert_eq!(
+///     accumulator.partial(),
+///     Some(json!({ "title": "Rust", "tags": ["fa"] }))
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingJsonAccumulator {
+    buffer: String,
+    /// The containers that are still open, outermost first.
+    stack: Vec<Frame>,
+    /// The top-level value, once it is complete.
+    root: Option<Value>,
+    token: Token,
+    /// Set once the text stops being valid JSON; later text is only
+    /// buffered.
+    malformed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Frame {
+    Array(Vec<Value>),
+    Object {
+        map: serde_json::Map<String, Value>,
+        /// The key whose value is being parsed.
+        key: Option<String>,
+    },
+}
+
+impl Frame {
+    fn into_value(self) -> Value {
+        match self {
+            Frame::Array(values) => Value::Array(values),
+            Frame::Object { map, .. } => Value::Object(map),
+        }
+    }
+
+    /// Closes a copy of the container, adding `value` as its last element.
+    fn close_with(&self, value: Option<Value>) -> Value {
+        match self {
+            Frame::Array(values) => {
+                let mut values = values.clone();
+                values.extend(value);
+                Value::Array(values)
+            }
+            Frame::Object { map, key } => {
+                let mut map = map.clone();
+                if let (Some(key), Some(value)) = (key, value) {
+                    map.insert(key.clone(), value);
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+/// The token being read when a delta ends.
+#[derive(Debug, Clone, Default, PartialEq)]
+enum Token {
+    #[default]
+    None,
+    /// A string, with its escapes not yet decoded.
+    String {
+        raw: String,
+        escaped: bool,
+        is_key: bool,
+    },
+    /// A number or a literal such as `true`.
+    Scalar(String),
+}
+
+impl StreamingJsonAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a text delta.
+    ///
+    /// The delta is parsed as it arrives, so the cost of a push does not
+    /// grow with the text accumulated before it.
+    pub fn push(&mut self, delta: &str) {
+        self.buffer.push_str(delta);
+        if self.malformed {
+            return;
+        }
+        for c in delta.chars() {
+            if !self.feed(c) {
+                self.malformed = true;
+                return;
+            }
+        }
+    }
+
+    /// Appends the text parts of the first candidate of a streamed chunk.
+    pub fn push_response(&mut self, response: &GenerateContentResponse) {
+        let parts = response
+            .candidates
+            .first()
+            .and_then(|candidate| candidate.content.as_ref())
+            .map(|content| content.parts.as_slice())
+            .unwrap_or_default();
+        for part in parts {
+            if let Part::Text { text } = part {
+                self.push(text);
+            }
+        }
+    }
+
+    /// The raw text accumulated so far.
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The document parsed so far, with any open strings, arrays and
+    /// objects closed.
+    ///
+    /// Trailing fragments that cannot be completed, such as a half-written
+    /// key or literal, are dropped, as is any text after the point where
+    /// the document stops being valid JSON. Returns `None` if nothing
+    /// parseable has arrived yet.
+    pub fn partial(&self) -> Option<Value> {
+        let mut value = match &self.token {
+            Token::String {
+                raw, is_key: false, ..
+            } => decode_partial(raw).map(Value::String),
+            Token::Scalar(text) => serde_json::from_str(text).ok(),
+            _ => None,
+        };
+        for frame in self.stack.iter().rev() {
+            value = Some(frame.close_with(value));
+        }
+        value.or_else(|| self.root.clone())
+    }
+
+    /// Parses the complete document once the stream has ended.
+    pub fn finish(self) -> Result<Value, GeminiError> {
+        serde_json::from_str(&self.buffer).map_err(|error| GeminiError::Json {
+            data: self.buffer,
+            error,
+        })
+    }
+
+    /// Advances the parser by one character, returning `false` if the text
+    /// is not valid JSON.
+    fn feed(&mut self, c: char) -> bool {
+        match std::mem::take(&mut self.token) {
+            Token::String {
+                mut raw,
+                escaped,
+                is_key,
+            } => {
+                if escaped || c != '"' {
+                    raw.push(c);
+                    self.token = Token::String {
+                        raw,
+                        escaped: !escaped && c == '\\',
+                        is_key,
+                    };
+                    return true;
+                }
+                return match decode(&raw) {
+                    Some(text) if is_key => self.set_key(text),
+                    Some(text) => self.complete(Value::String(text)),
+                    None => false,
+                };
+            }
+            Token::Scalar(mut text) => {
+                if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.') {
+                    text.push(c);
+                    self.token = Token::Scalar(text);
+                    return true;
+                }
+                let completed = serde_json::from_str(&text).is_ok_and(|value| self.complete(value));
+                if !completed {
+                    return false;
+                }
+            }
+            Token::None => {}
+        }
+
+        match c {
+            '"' => {
+                let is_key = matches!(self.stack.last(), Some(Frame::Object { key: None, .. }));
+                if !is_key && !self.accepts_value() {
+                    return false;
+                }
+                self.token = Token::String {
+                    raw: String::new(),
+                    escaped: false,
+                    is_key,
+                };
+                true
+            }
+            '{' | '[' if self.accepts_value() => {
+                self.stack.push(if c == '{' {
+                    Frame::Object {
+                        map: serde_json::Map::new(),
+                        key: None,
+                    }
+                } else {
+                    Frame::Array(vec![])
+                });
+                true
+            }
+            '}' | ']' => {
+                let closes = match self.stack.last() {
+                    Some(Frame::Object { key: None, .. }) => c == '}',
+                    Some(Frame::Array(_)) => c == ']',
+                    _ => false,
+                };
+                if !closes {
+                    return false;
+                }
+                match self.stack.pop() {
+                    Some(frame) => self.complete(frame.into_value()),
+                    None => false,
+                }
+            }
+            ',' | ':' => !self.stack.is_empty(),
+            c if c.is_whitespace() => true,
+            c if self.accepts_value() => {
+                self.token = Token::Scalar(c.to_string());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a value can start here, i.e. not in place of an object key
+    /// or after the top-level value.
+    fn accepts_value(&self) -> bool {
+        match self.stack.last() {
+            Some(Frame::Array(_)) => true,
+            Some(Frame::Object { key, .. }) => key.is_some(),
+            None => self.root.is_none(),
+        }
+    }
+
+    fn set_key(&mut self, text: String) -> bool {
+        match self.stack.last_mut() {
+            Some(Frame::Object {
+                key: key @ None, ..
+            }) => {
+                *key = Some(text);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds a completed value to the innermost open container.
+    fn complete(&mut self, value: Value) -> bool {
+        match self.stack.last_mut() {
+            Some(Frame::Array(values)) => {
+                values.push(value);
+                true
+            }
+            Some(Frame::Object { map, key }) => match key.take() {
+                Some(key) => {
+                    map.insert(key, value);
+                    true
+                }
+                None => false,
+            },
+            None if self.root.is_none() => {
+                self.root = Some(value);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Decodes the escapes of a complete string body.
+fn decode(raw: &str) -> Option<String> {
+    serde_json::from_str(&format!("\"{raw}\"")).ok()
+}
+
+/// Decodes a string body that may end partway through an escape such as
+/// `\u00e9`, dropping the incomplete escape.
+fn decode_partial(raw: &str) -> Option<String> {
+    decode(raw).or_else(|| {
+        raw.char_indices()
+            .rev()
+            .take(6)
+            .find_map(|(end, _)| decode(&raw[..end]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::StreamingJsonAccumulator;
+
+    #[test]
+    fn partial_handles_truncated_nested_structures() {
+        let mut accumulator = StreamingJsonAccumulator::new();
+        assert_eq!(accumulator.partial(), None);
+
+        let expected = [
+            (
+                r#"{"recipes": [{"name": "Pan"#,
+                json!({ "recipes": [{ "name": "Pan" }] }),
+            ),
+            (
+                r#"cakes", "steps": [1, 2,"#,
+                json!({ "recipes": [{ "name": "Pancakes", "steps": [1, 2] }] }),
+            ),
+            (
+                r#" 3], "vegan": tr"#,
+                json!({ "recipes": [{ "name": "Pancakes", "steps": [1, 2, 3] }] }),
+            ),
+            (
+                r#"ue}], "no"#,
+                json!({ "recipes": [{ "name": "Pancakes", "steps": [1, 2, 3], "vegan": true }] }),
+            ),
+            (
+                r#"te": "Serves \"four"#,
+                json!({
+                    "recipes": [{ "name": "Pancakes", "steps": [1, 2, 3], "vegan": true }],
+                    "note": "Serves \"four"
+                }),
+            ),
+        ];
+        for (delta, value) in expected {
+            accumulator.push(delta);
+            assert_eq!(accumulator.partial(), Some(value), "after {delta:?}");
+        }
+
+        accumulator.push(r#"\""}"#);
+        assert_eq!(
+            accumulator.finish().expect("document should be complete"),
+            json!({
+                "recipes": [{ "name": "Pancakes", "steps": [1, 2, 3], "vegan": true }],
+                "note": "Serves \"four\""
+            })
+        );
+    }
+
+    #[test]
+    fn partial_matches_the_document_when_fed_one_character_at_a_time() {
+        let document = r#"{"a": [1, -2.5e3, true, null, {"b": "caf\u00e9 \"x\""}], "c": {}}"#;
+        let mut accumulator = StreamingJsonAccumulator::new();
+        for (index, c) in document.char_indices() {
+            accumulator.push(&c.to_string());
+            // Every prefix yields something once the first brace is read.
+            assert!(
+                accumulator.partial().is_some(),
+                "after {:?}",
+                &document[..=index]
+            );
+        }
+        let expected: serde_json::Value = serde_json::from_str(document).expect("valid JSON");
+        assert_eq!(accumulator.partial(), Some(expected.clone()));
+        assert_eq!(accumulator.finish().expect("complete document"), expected);
+    }
+
+    #[test]
+    fn partial_drops_incomplete_escapes() {
+        let mut accumulator = StreamingJsonAccumulator::new();
+        accumulator.push(r#"["caf\u00"#);
+        assert_eq!(accumulator.partial(), Some(json!(["caf"])));
+        accumulator.push(r#"e9"#);
+        assert_eq!(accumulator.partial(), Some(json!(["café"])));
+    }
+
+    #[test]
+    fn partial_keeps_the_valid_prefix_of_malformed_text() {
+        let mut accumulator = StreamingJsonAccumulator::new();
+        accumulator.push(r#"{"a": [1, 2}, "b": 3"#);
+        assert_eq!(accumulator.partial(), Some(json!({ "a": [1, 2] })));
+        assert!(accumulator.finish().is_err());
+    }
+}
@@ -2,7 +2,6 @@
 use crate::GeminiError;
 
 #[cfg(feature = "tracing")]
-#[allow(dead_code)]
 pub(crate) struct SpanGuard(Option<tracing::Span>);
 
 #[cfg(not(feature = "tracing"))]
@@ -19,6 +18,41 @@ impl SpanGuard {
         Self
     }
 
+    /// Runs `future` inside the span, so events it emits are attributed to
+    /// it without holding an entered guard across `.await` points.
+    pub(crate) async fn instrument<F: std::future::Future>(&self, future: F) -> F::Output {
+        #[cfg(feature = "tracing")]
+        if let Some(span) = &self.0 {
+            return tracing::Instrument::instrument(future, span.clone()).await;
+        }
+        future.await
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record<V: tracing::Value>(&self, field: &str, value: V) {
+        if let Some(span) = &self.0 {
+            span.record(field, value);
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn record<V>(&self, _field: &str, _value: V) {}
+
+    pub(crate) fn record_status(&self, status: u16) {
+        self.record("status_code", status);
+    }
+
+    pub(crate) fn record_usage(&self, usage: &crate::types::UsageMetadata) {
+        if let Some(count) = usage.prompt_token_count {
+            self.record("prompt_token_count", count);
+        }
+        if let Some(count) = usage.candidates_token_count {
+            self.record("candidates_token_count", count);
+        }
+        if let Some(count) = usage.total_token_count {
+            self.record("total_token_count", count);
+        }
+    }
 }
 
 #[cfg(feature = "tracing")]
@@ -28,6 +62,20 @@ pub(crate) fn gemini_error_kind(error: &GeminiError) -> &'static str {
         GeminiError::EventSource(_) => "event_source",
         GeminiError::Api(_) => "api",
         GeminiError::Json { .. } => "json",
+        GeminiError::Blocked(_) => "blocked",
+        GeminiError::DimensionMismatch { .. } => "dimension_mismatch",
+        GeminiError::InvalidRequest(_) => "invalid_request",
+        GeminiError::UnknownModel(_) => "unknown_model",
+        GeminiError::Timeout => "timeout",
+        GeminiError::Io(_) => "io",
+        GeminiError::FunctionExecution { .. } => "function_execution",
+        GeminiError::FileProcessingFailed(_) => "file_processing_failed",
+        GeminiError::InvalidApiKey(_) => "invalid_api_key",
+        GeminiError::RateLimited { .. } => "rate_limited",
+        GeminiError::ResponseTooLarge { .. } => "response_too_large",
+        GeminiError::TooManyTurns { .. } => "too_many_turns",
+        GeminiError::EmbeddingCountMismatch { .. } => "embedding_count_mismatch",
+        GeminiError::EmbeddingBatchFailed { .. } => "embedding_batch_failed",
     }
 }
 
@@ -0,0 +1,172 @@
+use crate::GeminiError;
+#[cfg(any(test, feature = "testing"))]
+use serde_json::Value;
+
+/// Sends HTTP requests to the Gemini API.
+///
+/// Every request made by [`crate::GeminiClient`] and its sub-clients,
+/// including streams and file uploads, goes through a [`ReqwestTransport`]
+/// unless another transport is set with
+/// [`crate::GeminiClient::with_transport`], e.g. a `MockTransport` from the
+/// `testing` feature.
+#[async_trait::async_trait]
+pub trait Transport: std::fmt::Debug + Send + Sync {
+    /// Sends `request`, which already carries the client's credentials, and
+    /// returns the response.
+    ///
+    /// Non-success statuses are returned as `Ok` and turned into
+    /// [`GeminiError::Api`] by the client, which also reads the body within
+    /// the [`crate::GeminiClient::with_max_response_bytes`] limit.
+    async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response, GeminiError>;
+}
+
+/// The default [`Transport`], backed by a [`reqwest::Client`].
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response, GeminiError> {
+        Ok(self.client.execute(request).await?)
+    }
+}
+
+/// A request received by a [`MockTransport`].
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct MockRequest {
+    pub method: reqwest::Method,
+    /// The full URL, including the `key` query parameter.
+    pub url: String,
+    pub headers: reqwest::header::HeaderMap,
+    /// The JSON body, or [`Value::Null`] for requests without one, such as
+    /// `GET`s and file uploads.
+    pub body: Value,
+}
+
+/// A [`Transport`] that returns queued responses and records the requests
+/// it receives, for testing code built on [`crate::GeminiClient`] without
+/// network access.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use gemini_client_rs::{GeminiClient, MockTransport};
+/// # use gemini_client_rs::types::GenerateContentRequest;
+/// # use serde_json::json;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), gemini_client_rs::GeminiError> {
+/// let transport = Arc::new(MockTransport::new());
+/// transport.push_json(&json!({
+///     "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hi!" }] } }]
+/// }));
+///
+/// let client = GeminiClient::new("key".to_string()).with_transport(transport.clone());
+/// let response = client
+///     .generate_content("gemini-2.5-flash", &GenerateContentRequest::default())
+///     .await?;
+/// assert_eq!(response.candidates.len(), 1);
+/// assert_eq!(transport.requests().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: std::sync::Mutex<std::collections::VecDeque<http::Response<String>>>,
+    requests: std::sync::Mutex<Vec<MockRequest>>,
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a JSON response with the given status code and raw body.
+    pub fn push_response(&self, status: u16, body: impl Into<String>) {
+        self.push(status, "application/json", body.into());
+    }
+
+    /// Queues a successful response with `body` serialized as JSON.
+    pub fn push_json<T: serde::Serialize>(&self, body: &T) {
+        let body = serde_json::to_string(body).expect("mock response should serialize");
+        self.push_response(200, body);
+    }
+
+    /// Queues a server-sent events response with one event per item, as
+    /// returned by `streamGenerateContent`.
+    pub fn push_events<T: serde::Serialize>(&self, events: &[T]) {
+        let body = events
+            .iter()
+            .map(|event| {
+                let data = serde_json::to_string(event).expect("mock event should serialize");
+                format!("data: {data}\r\n\r\n")
+            })
+            .collect();
+        self.push(200, "text/event-stream", body);
+    }
+
+    /// Every request sent so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests
+            .lock()
+            .expect("mock transport lock poisoned")
+            .clone()
+    }
+
+    fn push(&self, status: u16, content_type: &str, body: String) {
+        let response = http::Response::builder()
+            .status(status)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body)
+            .expect("mock response should build");
+        self.responses
+            .lock()
+            .expect("mock transport lock poisoned")
+            .push_back(response);
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+#[async_trait::async_trait]
+impl Transport for MockTransport {
+    /// Returns the next queued response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no responses are queued.
+    async fn send(&self, request: reqwest::Request) -> Result<reqwest::Response, GeminiError> {
+        let url = request.url().to_string();
+        let body = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .and_then(|body| serde_json::from_slice(body).ok())
+            .unwrap_or_default();
+        self.requests
+            .lock()
+            .expect("mock transport lock poisoned")
+            .push(MockRequest {
+                method: request.method().clone(),
+                url: url.clone(),
+                headers: request.headers().clone(),
+                body,
+            });
+        let response = self
+            .responses
+            .lock()
+            .expect("mock transport lock poisoned")
+            .pop_front();
+        let response =
+            response.unwrap_or_else(|| panic!("MockTransport has no queued response for {url}"));
+        Ok(response.into())
+    }
+}
@@ -1,6 +1,10 @@
-use std::collections::{BTreeSet, HashMap};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// Re-exported for building [`Schema::properties`] and
+/// [`FunctionParameters::properties`].
+pub use indexmap::IndexMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -17,10 +21,17 @@ pub struct Schema {
     pub enum_values: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<Schema>>,
+    /// Serialized in declaration order, like
+    /// [`FunctionParameters::properties`], so identical schemas produce
+    /// identical request bytes.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub properties: Option<HashMap<String, Schema>>,
+    pub properties: Option<IndexMap<String, Schema>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<Vec<String>>,
+    /// The order in which the model should emit `properties`. Properties
+    /// are otherwise emitted in alphabetical order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property_ordering: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
@@ -36,7 +47,6 @@ pub enum SchemaType {
     Object,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum Role {
@@ -45,11 +55,9 @@ pub enum Role {
     Model,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateContentRequest {
-
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_instruction: Option<Content>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -58,15 +66,366 @@ pub struct GenerateContentRequest {
     pub tools: Vec<Tool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_config: Option<ToolConfig>,
+    /// Settings for blocking unsafe content, at most one per category.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub safety_settings: Vec<SafetySetting>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub generation_config: Option<GenerationConfig>,
+    /// The name of a cached content resource to use as context, e.g.
+    /// `cachedContents/abc123`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_content: Option<String>,
+    /// Labels attributing the request's cost in billing reports, e.g. by
+    /// tenant. Supported by Vertex AI.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
 }
 
+/// A chat message in the OpenAI format, for
+/// [`GenerateContentRequest::from_openai_messages`].
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-#[serde(untagged, rename_all = "camelCase", rename_all_fields = "camelCase")]
-pub enum Tool {
+pub struct OpenAiMessage {
+    /// `system`, `user` or `assistant`.
+    pub role: String,
+    pub content: String,
+}
+
+impl GenerateContentRequest {
+    /// The approximate limit on the total request size when sending inline
+    /// data. Larger media should be uploaded through the Files API.
+    pub const MAX_INLINE_REQUEST_BYTES: usize = 20 * 1024 * 1024;
+
+    /// The size of the request body as serialized JSON, in bytes.
+    ///
+    /// [`InlineData::data`] is already base64 encoded, so this includes the
+    /// ~4/3 expansion of the raw media bytes.
+    pub fn estimated_bytes(&self) -> usize {
+        struct CountingWriter(usize);
+
+        impl std::io::Write for CountingWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0 += buf.len();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = CountingWriter(0);
+        // Serializing plain data into a writer that never fails cannot error.
+        let _ = serde_json::to_writer(&mut writer, self);
+        writer.0
+    }
+
+    /// Whether the request is larger than
+    /// [`GenerateContentRequest::MAX_INLINE_REQUEST_BYTES`].
+    pub fn exceeds_inline_limit(&self) -> bool {
+        self.estimated_bytes() > Self::MAX_INLINE_REQUEST_BYTES
+    }
+
+    /// Checks that no [`InlineData`] part is larger than
+    /// [`InlineData::MAX_BYTES`] and that the whole request fits in
+    /// [`GenerateContentRequest::MAX_INLINE_REQUEST_BYTES`], suggesting the
+    /// Files API otherwise.
+    ///
+    /// Not run by [`GenerateContentRequest::validate`], since the per-part
+    /// limit depends on the endpoint and model.
+    pub fn validate_inline_sizes(&self) -> Result<(), crate::GeminiError> {
+        let oversized = self
+            .system_instruction
+            .iter()
+            .chain(&self.contents)
+            .flat_map(|content| &content.parts)
+            .find_map(|part| match part {
+                Part::InlineData { data, .. } if data.decoded_len() > InlineData::MAX_BYTES => {
+                    Some(data)
+                }
+                _ => None,
+            });
+        if let Some(data) = oversized {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "inline data ({}) is {} bytes, over the {} byte limit; upload it with `FilesClient::upload_file` and reference it with `Part::from_file` instead",
+                data.mime_type,
+                data.decoded_len(),
+                InlineData::MAX_BYTES
+            )));
+        }
+        if self.exceeds_inline_limit() {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "request is over the {} byte limit; upload large media with `FilesClient::upload_file` instead",
+                Self::MAX_INLINE_REQUEST_BYTES
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks the request against constraints the API enforces, so they
+    /// surface as a descriptive client-side error.
+    pub fn validate(&self) -> Result<(), crate::GeminiError> {
+        if let Some(thinking_config) = self
+            .generation_config
+            .as_ref()
+            .and_then(|config| config.thinking_config.as_ref())
+        {
+            thinking_config.validate()?;
+        }
+        let unsupported_system_part = self
+            .system_instruction
+            .iter()
+            .flat_map(|content| &content.parts)
+            .find(|part| {
+                !matches!(
+                    part,
+                    Part::Text { .. } | Part::InlineData { .. } | Part::FileData { .. }
+                )
+            });
+        if let Some(part) = unsupported_system_part {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "`system_instruction` only supports text, inline data and file data parts, got {part:?}"
+            )));
+        }
+        self.system_instruction
+            .iter()
+            .chain(&self.contents)
+            .flat_map(|content| &content.parts)
+            .try_for_each(|part| match part {
+                Part::InlineData { data, .. } => data.validate(),
+                Part::FileData { data, .. } => data.validate(),
+                _ => Ok(()),
+            })
+    }
+
+    /// Adds a billing label, see [`GenerateContentRequest::labels`].
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the system instruction to a single text part.
+    ///
+    /// The API does not accept a `system` role, so the instruction is sent
+    /// without a role.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_instruction = Some(Content {
+            parts: vec![Part::text(prompt)],
+            role: None,
+        });
+        self
+    }
+
+    /// Sets the system instruction to `content`, e.g. text alongside a
+    /// reference image as [`Part::InlineData`] or [`Part::FileData`].
+    ///
+    /// The role is cleared, as with
+    /// [`GenerateContentRequest::with_system_prompt`]. Other part types are
+    /// rejected by [`GenerateContentRequest::validate`].
+    pub fn with_system_content(mut self, content: Content) -> Self {
+        self.system_instruction = Some(Content {
+            role: None,
+            ..content
+        });
+        self
+    }
+
+    /// Forces the model to call the named function.
+    ///
+    /// Sets `tool_config` to [`FunctionCallingMode::Any`] with `name` as the
+    /// only allowed function name. The function must also be declared in
+    /// `tools`.
+    ///
+    /// ```rust
+    /// # use gemini_client_rs::gemini_chat;
+    /// let req = gemini_chat!(user("What's the weather in Paris?"))
+    ///     .force_function("get_weather");
+    /// ```
+    pub fn force_function(mut self, name: impl Into<String>) -> Self {
+        self.tool_config
+            .get_or_insert_with(ToolConfig::default)
+            .function_calling_config = Some(FunctionCallingConfig {
+            mode: FunctionCallingMode::Any,
+            allowed_function_names: vec![name.into()],
+        });
+        self
+    }
+
+    /// Extends the conversation with a truncated response, e.g. one that
+    /// stopped with [`FinishReason::MaxTokens`], and a user turn asking the
+    /// model to continue.
+    ///
+    /// Sending the returned request yields the next segment of the output,
+    /// which can be appended to the previous text as-is. If the output hit
+    /// [`FinishReason::MaxTokens`] right after a letter, it may have been cut
+    /// off mid-word, so the model is asked to resume from the exact next
+    /// character and the segments join without a stray space. Text ending in
+    /// whitespace, punctuation or a digit is treated as a word boundary.
+    pub fn continue_from(mut self, response: &GenerateContentResponse) -> Self {
+        let candidate = response.candidates.first();
+        let content = candidate.and_then(|candidate| candidate.content.clone());
+
+        let hit_token_limit = candidate
+            .is_some_and(|candidate| candidate.finish_reason == Some(FinishReason::MaxTokens));
+        let ends_mid_word = hit_token_limit
+            && content
+                .as_ref()
+                .and_then(|content| {
+                    content.parts.iter().rev().find_map(|part| match part {
+                        Part::Text { text } if !text.is_empty() => text.chars().last(),
+                        _ => None,
+                    })
+                })
+                .is_some_and(char::is_alphabetic);
+
+        if let Some(content) = content {
+            self.contents.push(Content {
+                role: Some(Role::Model),
+                ..content
+            });
+        }
+
+        let instruction = if ends_mid_word {
+            "Your previous response was cut off mid-word. Continue from the exact next \
+             character, completing the word, without repeating any earlier text."
+        } else {
+            "Your previous response was cut off. Continue exactly where you left off, \
+             without repeating any earlier text."
+        };
+        self.contents.push(Content::multimodal(
+            Role::User,
+            vec![Part::text(instruction)],
+        ));
+        self
+    }
+
+    /// Enables the built-in code execution tool.
+    pub fn enable_code_execution(mut self) -> Self {
+        self.tools.push(Tool::CodeExecution {
+            code_execution: serde_json::json!({}),
+        });
+        self
+    }
+
+    /// Enables grounding with Google Maps.
+    pub fn enable_google_maps(mut self) -> Self {
+        self.tools.push(Tool::GoogleMaps {
+            google_maps: serde_json::json!({}),
+        });
+        self
+    }
+
+    /// Appends the result of a function call executed by the caller.
+    ///
+    /// The response is sent in a [`Role::User`] turn. Responses to parallel
+    /// calls are grouped into the same turn, as long as they are added
+    /// one after another. The model turn with the calls must already be in
+    /// `contents`.
+    pub fn add_function_response(&mut self, name: &str, id: Option<&str>, content: Value) {
+        let part = Part::FunctionResponse {
+            response: FunctionResponse {
+                id: id.map(str::to_string),
+                name: name.to_string(),
+                response: FunctionResponsePayload { content },
+            },
+        };
+
+        match self.contents.last_mut() {
+            Some(last)
+                if last.role == Some(Role::User)
+                    && !last.parts.is_empty()
+                    && last
+                        .parts
+                        .iter()
+                        .all(|part| matches!(part, Part::FunctionResponse { .. })) =>
+            {
+                last.parts.push(part)
+            }
+            _ => self
+                .contents
+                .push(Content::multimodal(Role::User, vec![part])),
+        }
+    }
 
+    /// Merges adjacent `contents` with the same role into a single turn by
+    /// concatenating their parts, since the API rejects consecutive turns
+    /// from the same role.
+    pub fn normalize_roles(&mut self) {
+        let contents = std::mem::take(&mut self.contents);
+        for content in contents {
+            match self.contents.last_mut() {
+                Some(last) if last.role == content.role => last.parts.extend(content.parts),
+                _ => self.contents.push(content),
+            }
+        }
+    }
+
+    /// Builds a request from OpenAI-style chat messages, e.g. when
+    /// migrating from another provider.
+    ///
+    /// `system` messages become the system instruction, `assistant`
+    /// messages [`Role::Model`] turns and `user` messages [`Role::User`]
+    /// turns. Adjacent turns with the same role are merged, see
+    /// [`GenerateContentRequest::normalize_roles`]. Any other role, such as
+    /// `tool`, is rejected.
+    pub fn from_openai_messages(messages: &[OpenAiMessage]) -> Result<Self, crate::GeminiError> {
+        let mut request = Self::default();
+        let mut system_parts = vec![];
+        for message in messages {
+            let role = match message.role.as_str() {
+                "system" | "developer" => {
+                    system_parts.push(Part::text(&message.content));
+                    continue;
+                }
+                "user" => Role::User,
+                "assistant" => Role::Model,
+                role => {
+                    return Err(crate::GeminiError::InvalidRequest(format!(
+                        "unsupported OpenAI message role `{role}`"
+                    )))
+                }
+            };
+            request.contents.push(Content::multimodal(
+                role,
+                vec![Part::text(&message.content)],
+            ));
+        }
+
+        if !system_parts.is_empty() {
+            request.system_instruction = Some(Content {
+                parts: system_parts,
+                role: None,
+            });
+        }
+        request.normalize_roles();
+        Ok(request)
+    }
+
+    /// Lets the model decide between calling a function and answering in
+    /// natural language ([`FunctionCallingMode::Auto`]).
+    pub fn auto_tools(mut self) -> Self {
+        self.tool_config
+            .get_or_insert_with(ToolConfig::default)
+            .function_calling_config = Some(FunctionCallingConfig {
+            mode: FunctionCallingMode::Auto,
+            allowed_function_names: vec![],
+        });
+        self
+    }
+
+    /// Scopes grounding tools such as Google Maps, e.g. to the user's
+    /// location. Any function calling config is kept.
+    pub fn with_retrieval_config(mut self, retrieval_config: RetrievalConfig) -> Self {
+        self.tool_config
+            .get_or_insert_with(ToolConfig::default)
+            .retrieval_config = Some(retrieval_config);
+        self
+    }
+}
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[non_exhaustive]
+#[serde(untagged, rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Tool {
     // will work for both v1 and v2 models
     #[serde(rename = "function_declaration")]
     FunctionDeclaration(ToolConfigFunctionDeclaration),
@@ -85,23 +444,62 @@ pub enum Tool {
         url_context: serde_json::Value,
     },
 
+    /* NOTE: Grounding with Google Maps, e.g. for location-aware questions */
+    GoogleMaps {
+        google_maps: serde_json::Value,
+    },
+
     /* NOTE: Used by v2 models if they have the code execution built in */
     CodeExecution {
         code_execution: serde_json::Value,
     },
 }
 
+impl Tool {
+    /// The built-in Google Search tool used by gemini-2.x models. The
+    /// configuration object is empty; `DynamicRetrieval` is its deprecated
+    /// v1 counterpart.
+    pub fn google_search() -> Self {
+        Tool::GoogleSearch {
+            google_search: serde_json::json!({}),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_calling_config: Option<FunctionCallingConfig>,
+    /// Configures grounding tools, e.g. the location used by
+    /// [`Tool::GoogleMaps`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieval_config: Option<RetrievalConfig>,
+}
+
+/// Retrieval settings shared by the grounding tools.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievalConfig {
+    /// The user's location.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lat_lng: Option<LatLng>,
+    /// The user's language, e.g. `en-US`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_code: Option<String>,
+}
 
-    pub function_calling_config: FunctionCallingConfig,
+/// A latitude/longitude pair, in degrees.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatLng {
+    pub latitude: f64,
+    pub longitude: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FunctionCallingConfig {
-
     pub mode: FunctionCallingMode,
     /// A set of function names that, when provided, limits the functions the
     /// model will call.
@@ -109,7 +507,7 @@ pub struct FunctionCallingConfig {
     /// This should only be set when the Mode is ANY. Function names should match
     /// [FunctionDeclaration.name]. With mode set to ANY, model will predict a
     /// function call from the set of function names provided.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub allowed_function_names: Vec<String>,
 }
 
@@ -140,15 +538,113 @@ pub enum FunctionCallingMode {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Content {
-
-
     #[serde(default)]
     pub parts: Vec<Part>,
     // Optional. The producer of the content. Must be either 'user' or 'model'.
     // Useful to set for multi-turn conversations, otherwise can be left blank or unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<Role>,
 }
 
+impl Content {
+    /// Creates content from `role` and a mix of parts, e.g. text alongside
+    /// images and files.
+    pub fn multimodal(role: Role, parts: Vec<Part>) -> Self {
+        Self {
+            parts,
+            role: Some(role),
+        }
+    }
+
+    /// The model turn carrying function calls, for replaying them in the
+    /// conversation history. Function calls belong to [`Role::Model`].
+    pub fn function_calls(calls: impl IntoIterator<Item = FunctionCall>) -> Self {
+        Self::multimodal(
+            Role::Model,
+            calls
+                .into_iter()
+                .map(|call| Part::FunctionCall { call })
+                .collect(),
+        )
+    }
+
+    /// The turn returning function results to the model. The API expects
+    /// function responses under [`Role::User`].
+    pub fn function_responses(responses: impl IntoIterator<Item = FunctionResponse>) -> Self {
+        Self::multimodal(
+            Role::User,
+            responses
+                .into_iter()
+                .map(|response| Part::FunctionResponse { response })
+                .collect(),
+        )
+    }
+}
+
+/// Fluent builder for multimodal [`Content`].
+///
+/// ```rust,no_run
+/// # use gemini_client_rs::types::{ContentBuilder, Role};
+/// # fn main() -> Result<(), gemini_client_rs::GeminiError> {
+/// let content = ContentBuilder::new(Role::User)
+///     .text("Describe these images and summarise the report.")
+///     .image_path("first.png")?
+///     .image_path("second.jpg")?
+///     .file_uri("application/pdf", "https://generativelanguage.googleapis.com/v1beta/files/abc")
+///     .build();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContentBuilder {
+    role: Option<Role>,
+    parts: Vec<Part>,
+}
+
+impl ContentBuilder {
+    pub fn new(role: Role) -> Self {
+        Self {
+            role: Some(role),
+            parts: vec![],
+        }
+    }
+
+    /// Appends a text part.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.parts.push(Part::text(text));
+        self
+    }
+
+    /// Reads an image from disk and appends it as inline data, guessing the
+    /// MIME type from the file extension.
+    pub fn image_path(self, path: impl AsRef<std::path::Path>) -> Result<Self, crate::GeminiError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        Ok(self.inline(crate::get_mime_type(path), data))
+    }
+
+    /// Appends a reference to a file, e.g. one uploaded via the Files API.
+    pub fn file_uri(mut self, mime_type: impl Into<String>, uri: impl Into<String>) -> Self {
+        self.parts.push(Part::file_data(mime_type, uri));
+        self
+    }
+
+    /// Appends raw bytes as base64-encoded inline data.
+    pub fn inline(mut self, mime_type: impl Into<String>, bytes: impl AsRef<[u8]>) -> Self {
+        self.parts.push(Part::inline_data(
+            mime_type,
+            crate::base64_encode(bytes.as_ref()),
+        ));
+        self
+    }
+
+    pub fn build(self) -> Content {
+        Content {
+            parts: self.parts,
+            role: self.role,
+        }
+    }
+}
 
 pub trait GeminiSchema {
     fn schema() -> Schema;
@@ -159,7 +655,6 @@ pub trait GeminiTool {
 }
 
 impl GeminiSchema for String {
-
     fn schema() -> Schema {
         Schema {
             schema_type: SchemaType::String,
@@ -216,7 +711,6 @@ impl<T: GeminiSchema> GeminiSchema for Option<T> {
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerationConfig {
-
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub stop_sequences: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -258,13 +752,174 @@ pub struct GenerationConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking_config: Option<ThinkingConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub media_resolution: Option<String>,
+    pub media_resolution: Option<MediaResolution>,
+    /// Includes timestamps in transcripts of audio inputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_timestamp: Option<bool>,
+}
+
+impl GenerationConfig {
+    /// The maximum number of stop sequences the API accepts.
+    pub const MAX_STOP_SEQUENCES: usize = 5;
+
+    /// Sets the number of candidates to generate, which must be at least 1.
+    pub fn candidates(mut self, count: i32) -> Result<Self, crate::GeminiError> {
+        if count < 1 {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "`candidate_count` must be at least 1, got {count}"
+            )));
+        }
+        self.candidate_count = Some(count);
+        Ok(self)
+    }
+
+    /// Sets `response_modalities` from typed values, e.g.
+    /// `[Modality::Text, Modality::Image]` for image generation.
+    pub fn with_modalities(mut self, modalities: &[Modality]) -> Self {
+        self.response_modalities = modalities
+            .iter()
+            .map(|modality| modality.as_str().to_string())
+            .collect();
+        self
+    }
+
+    /// Requests log probabilities for the chosen tokens, plus the `top`
+    /// most likely alternatives at each step (1 to 5).
+    ///
+    /// Sets both `response_logprobs` and `logprobs`, since `logprobs` has no
+    /// effect on its own.
+    pub fn with_logprobs(mut self, top: u8) -> Result<Self, crate::GeminiError> {
+        if !(1..=5).contains(&top) {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "`logprobs` must be between 1 and 5, got {top}"
+            )));
+        }
+        self.response_logprobs = Some(true);
+        self.logprobs = Some(top.into());
+        Ok(self)
+    }
+
+    /// Sets the sequences that stop generation, up to
+    /// [`GenerationConfig::MAX_STOP_SEQUENCES`].
+    pub fn stop_sequences(
+        mut self,
+        stop_sequences: Vec<String>,
+    ) -> Result<Self, crate::GeminiError> {
+        if stop_sequences.len() > Self::MAX_STOP_SEQUENCES {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "at most {} `stop_sequences` are allowed, got {}",
+                Self::MAX_STOP_SEQUENCES,
+                stop_sequences.len()
+            )));
+        }
+        self.stop_sequences = stop_sequences;
+        Ok(self)
+    }
+
+    /// Returns a copy without settings that `model` does not support, based
+    /// on its metadata from [`crate::GeminiClient::get_model`].
+    ///
+    /// `top_k` and `top_p` are dropped when the model reports no default
+    /// for them, and `temperature` and `max_output_tokens` are clamped to
+    /// the model's limits. The model metadata does not report penalty
+    /// support, so `presence_penalty` and `frequency_penalty` are kept.
+    pub fn sanitized_for(&self, model: &Model) -> Self {
+        let mut config = self.clone();
+        if model.top_k.is_none() {
+            config.top_k = None;
+        }
+        if model.top_p.is_none() {
+            config.top_p = None;
+        }
+        if let (Some(temperature), Some(max_temperature)) =
+            (config.temperature, model.max_temperature)
+        {
+            config.temperature = Some(temperature.min(max_temperature.into()));
+        }
+        if let Some(max_output_tokens) = config.max_output_tokens {
+            if model.output_token_limit > 0 {
+                let limit = i32::try_from(model.output_token_limit).unwrap_or(i32::MAX);
+                config.max_output_tokens = Some(max_output_tokens.min(limit));
+            }
+        }
+        config
+    }
+
+    /// Requests free-form JSON output by setting `response_mime_type` to
+    /// `application/json`.
+    pub fn json_mode(mut self) -> Self {
+        self.response_mime_type = Some("application/json".to_string());
+        self
+    }
+
+    /// Requests JSON output conforming to `schema`, e.g. one produced by
+    /// `#[derive(GeminiSchema)]`.
+    pub fn json_mode_with_schema(self, schema: Schema) -> Self {
+        let mut config = self.json_mode();
+        config.response_schema = Some(schema);
+        config.response_json_schema = None;
+        config
+    }
+
+    /// Like [`GenerationConfig::json_mode_with_schema`], but has the model
+    /// emit the top-level properties in `ordering`, e.g. so that the fields
+    /// needed first arrive first when streaming.
+    pub fn with_ordered_response_schema(self, schema: Schema, ordering: Vec<String>) -> Self {
+        self.json_mode_with_schema(Schema {
+            property_ordering: Some(ordering),
+            ..schema
+        })
+    }
+
+    /// Requests JSON output conforming to a raw JSON Schema, sent as
+    /// `_responseJsonSchema`.
+    ///
+    /// Object keys are sorted recursively, so the request bytes don't depend
+    /// on how `schema` was built. To control the order in which the model
+    /// emits properties, set `propertyOrdering` in the schema instead.
+    pub fn with_response_schema_value(self, schema: Value) -> Self {
+        let mut config = self.json_mode();
+        config.response_schema = None;
+        config.response_json_schema = Some(sort_object_keys(schema));
+        config
+    }
+
+    /// Constrains the response to exactly one of `variants`, using the
+    /// `text/x.enum` response type. Read the choice back with
+    /// [`GenerateContentResponse::enum_value`].
+    pub fn enum_response(mut self, variants: Vec<String>) -> Self {
+        self.response_mime_type = Some("text/x.enum".to_string());
+        self.response_schema = Some(Schema {
+            schema_type: SchemaType::String,
+            enum_values: Some(variants),
+            ..Default::default()
+        });
+        self.response_json_schema = None;
+        self
+    }
+}
+
+/// Media resolution for the input media, controlling how many tokens images
+/// and video frames consume.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MediaResolution {
+    /// Media resolution has not been set.
+    #[default]
+    MediaResolutionUnspecified,
+    /// Media resolution set to low (64 tokens).
+    MediaResolutionLow,
+    /// Media resolution set to medium (256 tokens).
+    MediaResolutionMedium,
+    /// Media resolution set to high (zoomed reframing with 256 tokens).
+    MediaResolutionHigh,
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ToolConfigFunctionDeclaration {
-
     pub function_declarations: Vec<FunctionDeclaration>,
 }
 
@@ -281,11 +936,9 @@ pub struct DynamicRetrievalConfig {
     pub dynamic_threshold: f64,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct FunctionDeclaration {
-
     pub name: String,
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -294,18 +947,26 @@ pub struct FunctionDeclaration {
     pub response: Option<Schema>,
 }
 
-
 /// [DEPRECATED] Use [Schema] instead.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[allow(deprecated)]
 #[deprecated(since = "0.10.0", note = "Use Schema instead")]
 pub struct FunctionParameters {
     #[serde(rename = "type")]
     pub parameter_type: String,
+    /// Properties in declaration order, which is also the order they are
+    /// serialized in.
     #[allow(deprecated)]
-    pub properties: HashMap<String, ParameterProperty>,
+    pub properties: IndexMap<String, ParameterProperty>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<Vec<String>>,
+    /// The order in which the model should emit `properties`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "propertyOrdering"
+    )]
+    pub property_ordering: Option<Vec<String>>,
 }
 
 /// [DEPRECATED] Use [Schema] instead.
@@ -314,8 +975,6 @@ pub struct FunctionParameters {
 #[allow(deprecated)]
 #[deprecated(since = "0.10.0", note = "Use Schema instead")]
 pub enum ParameterProperty {
-
-
     String(ParameterPropertyString),
     Integer(ParameterPropertyInteger),
     Number(ParameterPropertyNumber),
@@ -323,44 +982,87 @@ pub enum ParameterProperty {
     Array(ParameterPropertyArray),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub struct ParameterPropertyArray {
+/// Defaults to an unconstrained string, e.g. for the `items` of a
+/// [`ParameterPropertyArray`] built with `..Default::default()`.
+#[allow(deprecated)]
+impl Default for ParameterProperty {
+    fn default() -> Self {
+        Self::String(ParameterPropertyString::default())
+    }
+}
 
+#[allow(deprecated)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterPropertyArray {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    #[allow(deprecated)]
     pub items: Box<ParameterProperty>,
-
+    /// The minimum number of items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<u64>,
+    /// The maximum number of items.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u64>,
+    /// Whether the value may be `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct ParameterPropertyString {
-
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none", rename = "enum")]
     pub enum_values: Option<Vec<String>>,
+    /// A format hint such as `date-time` or `int64`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Whether the value may be `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct ParameterPropertyInteger {
-
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// The inclusive lower bound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<i64>,
+    /// The inclusive upper bound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<i64>,
+    /// A format hint such as `date-time` or `int64`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Whether the value may be `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct ParameterPropertyNumber {
-
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// The inclusive lower bound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    /// The inclusive upper bound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    /// Whether the value may be `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 pub struct ParameterPropertyBoolean {
-
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Whether the value may be `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
 }
 
 /// Response from the model supporting multiple candidate responses.
@@ -377,80 +1079,364 @@ pub struct ParameterPropertyBoolean {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateContentResponse {
-
     #[serde(default)]
     pub candidates: Vec<Candidate>,
     pub prompt_feedback: Option<PromptFeedback>,
-    pub usage_metadata: UsageMetadata,
+    /// Token usage for the request. Omitted from some streamed chunks.
+    #[serde(default)]
+    pub usage_metadata: Option<UsageMetadata>,
+    /// The model version used to generate the response. Not every response,
+    /// e.g. every streamed chunk, includes it.
     #[serde(default)]
     pub model_version: Option<String>,
+    /// Identifier for the response, useful when reporting issues to support.
+    /// Not every response, e.g. every streamed chunk, includes it.
     #[serde(default)]
     pub response_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
-#[serde(rename_all = "camelCase")]
-pub struct EmbedContentRequest {
+impl GenerateContentResponse {
+    /// The model version used to generate the response, if reported.
+    pub fn model_version(&self) -> Option<&str> {
+        self.model_version.as_deref()
+    }
 
-    pub model: String,
-    pub content: Content,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub task_type: Option<TaskType>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub title: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub output_dimensionality: Option<i32>,
-}
+    /// The response identifier, if reported.
+    pub fn response_id(&self) -> Option<&str> {
+        self.response_id.as_deref()
+    }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum TaskType {
-    #[default]
-    TaskTypeUnspecified,
-    RetrievalQuery,
-    RetrievalDocument,
-    SemanticSimilarity,
-    Classification,
-    Clustering,
+    /// The response candidates.
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// Consumes the response, returning its candidates.
+    pub fn into_candidates(self) -> Vec<Candidate> {
+        self.candidates
+    }
+
+    /// The candidate whose `index` is `index`.
+    ///
+    /// The API does not guarantee that a candidate's position in
+    /// `candidates` matches its `index`. Candidates without an `index` are
+    /// matched by position.
+    pub fn candidate(&self, index: u32) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .enumerate()
+            .find(|(position, candidate)| candidate.index.unwrap_or(*position as u32) == index)
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// Token usage for the request, if reported.
+    pub fn usage(&self) -> Option<&UsageMetadata> {
+        self.usage_metadata.as_ref()
+    }
+
+    /// The total token count, or 0 if usage was not reported.
+    pub fn total_tokens(&self) -> u32 {
+        self.usage()
+            .and_then(|usage| usage.total_token_count)
+            .unwrap_or_default()
+    }
+
+    /// The data parts of every candidate whose MIME type starts with
+    /// `prefix`. See [`Candidate::parts_with_mime_prefix`].
+    pub fn parts_by_mime(&self, prefix: &str) -> Vec<&Part> {
+        self.candidates
+            .iter()
+            .flat_map(|candidate| candidate.parts_with_mime_prefix(prefix))
+            .collect()
+    }
+
+    /// The number of thinking tokens, billed as output, or 0 if not
+    /// reported.
+    pub fn thinking_tokens(&self) -> u32 {
+        self.usage()
+            .and_then(|usage| usage.thoughts_token_count)
+            .unwrap_or_default()
+    }
+
+    /// Returns the prompt feedback if the prompt was blocked, i.e. the API
+    /// returned no candidates and a `block_reason`. Feedback that only
+    /// carries safety ratings, as on some stream chunks, is not a block.
+    pub fn blocked(&self) -> Option<&PromptFeedback> {
+        if !self.candidates.is_empty() {
+            return None;
+        }
+        self.prompt_feedback
+            .as_ref()
+            .filter(|feedback| feedback.block_reason.is_some())
+    }
+
+    /// Converts a blocked prompt into [`crate::GeminiError::Blocked`] so it
+    /// can be handled on the error path instead of as an empty candidate
+    /// list.
+    pub fn into_result(self) -> Result<Self, crate::GeminiError> {
+        match self.blocked() {
+            Some(feedback) => Err(crate::GeminiError::Blocked(feedback.clone())),
+            None => Ok(self),
+        }
+    }
+
+    /// Whether the model has finished generating, i.e. the first candidate
+    /// has a `finish_reason`.
+    pub fn is_complete(&self) -> bool {
+        self.candidates
+            .first()
+            .is_some_and(|candidate| candidate.finish_reason.is_some())
+    }
+
+    /// Merges a subsequent streamed chunk into this response.
+    ///
+    /// Parts are appended to the candidate with the same index, with
+    /// consecutive text parts concatenated. Per-chunk metadata such as
+    /// `finish_reason` and `usage_metadata` is taken from `other` when
+    /// present, since the final chunk carries the totals.
+    pub fn merge(&mut self, other: GenerateContentResponse) {
+        for (position, candidate) in other.candidates.into_iter().enumerate() {
+            let existing = match candidate.index {
+                Some(index) => self
+                    .candidates
+                    .iter_mut()
+                    .find(|existing| existing.index == Some(index)),
+                None => self.candidates.get_mut(position),
+            };
+            match existing {
+                Some(existing) => existing.merge(candidate),
+                None => self.candidates.push(candidate),
+            }
+        }
+
+        if other.prompt_feedback.is_some() {
+            self.prompt_feedback = other.prompt_feedback;
+        }
+        if other.usage_metadata.is_some() {
+            self.usage_metadata = other.usage_metadata;
+        }
+        if other.model_version.is_some() {
+            self.model_version = other.model_version;
+        }
+        if other.response_id.is_some() {
+            self.response_id = other.response_id;
+        }
+    }
+
+    /// The variant chosen by the first candidate for a request made with
+    /// [`GenerationConfig::enum_response`].
+    pub fn enum_value(&self) -> Option<String> {
+        let text = self.candidates.first()?.text();
+        let value = text.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    /// Summarizes each candidate, e.g. to rank `candidate_count` samples by
+    /// `avg_logprobs`.
+    pub fn candidate_summaries(&self) -> Vec<CandidateSummary> {
+        self.candidates
+            .iter()
+            .enumerate()
+            .map(|(position, candidate)| CandidateSummary {
+                index: candidate.index.unwrap_or(position as u32),
+                finish_reason: candidate.finish_reason.clone(),
+                avg_logprobs: candidate.avg_logprobs,
+                token_count: candidate.token_count,
+                text: candidate.text(),
+            })
+            .collect()
+    }
+
+    /// A clone of the first candidate's content with the role set to
+    /// [`Role::Model`], ready to push onto `request.contents` for the next
+    /// turn.
+    pub fn model_content(&self) -> Option<Content> {
+        let content = self.candidates.first()?.content()?;
+        Some(Content {
+            role: Some(Role::Model),
+            ..content.clone()
+        })
+    }
+}
+
+/// Per-candidate overview returned by
+/// [`GenerateContentResponse::candidate_summaries`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct CandidateSummary {
+    /// The candidate index, falling back to its position in the response.
+    pub index: u32,
+    pub finish_reason: Option<FinishReason>,
+    /// Average log probability of the candidate's tokens. Higher means the
+    /// model was more confident.
+    pub avg_logprobs: Option<f32>,
+    pub token_count: Option<u32>,
+    /// The concatenated text parts, excluding thoughts.
+    pub text: String,
+}
+
+/// Response from the `countTokens` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CountTokensResponse {
+    /// The number of tokens the prompt tokenizes into.
+    #[serde(default)]
+    pub total_tokens: u32,
+    /// Number of tokens in the cached part of the prompt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_content_token_count: Option<u32>,
+    #[serde(default)]
+    pub prompt_tokens_details: Vec<ModalityTokenCount>,
+    #[serde(default)]
+    pub cache_tokens_details: Vec<ModalityTokenCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedContentRequest {
+    pub model: String,
+    pub content: Content,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<TaskType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dimensionality: Option<i32>,
+}
+
+impl EmbedContentRequest {
+    /// Checks the request against constraints the API enforces, so they
+    /// surface as a descriptive client-side error.
+    ///
+    /// A `title` is only accepted together with
+    /// [`TaskType::RetrievalDocument`].
+    pub fn validate(&self) -> Result<(), crate::GeminiError> {
+        if self.title.is_some() && self.task_type != Some(TaskType::RetrievalDocument) {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "`title` is only supported with task type RETRIEVAL_DOCUMENT, got {:?}",
+                self.task_type.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TaskType {
+    #[default]
+    TaskTypeUnspecified,
+    RetrievalQuery,
+    RetrievalDocument,
+    SemanticSimilarity,
+    Classification,
+    Clustering,
     QuestionAnswering,
     FactVerification,
     #[serde(other)]
     Other,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct EmbedContentResponse {
-
     pub embedding: ContentEmbedding,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ContentEmbedding {
-
     pub values: Vec<f32>,
 }
 
+impl ContentEmbedding {
+    fn check_dimensions(&self, other: &ContentEmbedding) -> Result<(), crate::GeminiError> {
+        if self.values.len() == other.values.len() {
+            Ok(())
+        } else {
+            Err(crate::GeminiError::DimensionMismatch {
+                expected: self.values.len(),
+                actual: other.values.len(),
+            })
+        }
+    }
+
+    /// Dot product of two embeddings of the same dimension.
+    pub fn dot_product(&self, other: &ContentEmbedding) -> Result<f32, crate::GeminiError> {
+        self.check_dimensions(other)?;
+        Ok(self
+            .values
+            .iter()
+            .zip(&other.values)
+            .map(|(a, b)| a * b)
+            .sum())
+    }
+
+    /// Cosine similarity of two embeddings of the same dimension.
+    ///
+    /// Returns `0.0` if either embedding has zero magnitude.
+    pub fn cosine_similarity(&self, other: &ContentEmbedding) -> Result<f32, crate::GeminiError> {
+        let dot = self.dot_product(other)?;
+        let norm = |values: &[f32]| values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let magnitude = norm(&self.values) * norm(&other.values);
+        if magnitude == 0.0 {
+            Ok(0.0)
+        } else {
+            Ok(dot / magnitude)
+        }
+    }
+
+    /// Ranks `docs` by cosine similarity to `query`, most similar first.
+    ///
+    /// Each entry is the index into `docs` paired with its similarity score.
+    pub fn rank_by_similarity(
+        query: &ContentEmbedding,
+        docs: &[ContentEmbedding],
+    ) -> Result<Vec<(usize, f32)>, crate::GeminiError> {
+        let mut ranked = docs
+            .iter()
+            .enumerate()
+            .map(|(index, doc)| Ok((index, query.cosine_similarity(doc)?)))
+            .collect::<Result<Vec<_>, crate::GeminiError>>()?;
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(ranked)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchEmbedContentsRequest {
-
     pub requests: Vec<EmbedContentRequest>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct BatchEmbedContentsResponse {
-
     pub embeddings: Vec<ContentEmbedding>,
 }
 
+/// A set of the feedback metadata the prompt specified in
+/// `GenerateContentRequest.contents`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFeedback {
+    /// If set, the prompt was blocked and no candidates are returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_reason: Option<BlockReason>,
+    /// Ratings for safety of the prompt. There is at most one rating per
+    /// category.
+    #[serde(default)]
+    pub safety_ratings: Vec<SafetyRating>,
+}
+
 /// Specifies the reason why the prompt was blocked.
+///
+/// Reasons added to the API after this release are kept verbatim in
+/// [`BlockReason::Unknown`] rather than failing to parse the response.
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum PromptFeedback {
+#[non_exhaustive]
+pub enum BlockReason {
     /// Default value. This value is unused.
     #[default]
     BlockReasonUnspecified,
@@ -466,6 +1452,10 @@ pub enum PromptFeedback {
     ProhibitedContent,
     /// Candidates blocked due to unsafe image generation content.
     ImageSafety,
+    /// A reason not known to this version of the client. Distinct from
+    /// [`BlockReason::Other`], which the API sends for unknown causes.
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
@@ -502,6 +1492,81 @@ pub struct ModalityTokenCount {
     pub token_count: u32,
 }
 
+impl UsageMetadata {
+    /// Output tokens per modality, summed across all candidates.
+    pub fn candidates_tokens_by_modality(&self) -> HashMap<Modality, u32> {
+        let mut counts = HashMap::new();
+        for detail in &self.candidates_tokens_details {
+            *counts.entry(detail.modality).or_default() += detail.token_count;
+        }
+        counts
+    }
+
+    /// Estimates the cost of the request at `pricing`'s rates.
+    ///
+    /// Thinking tokens are billed as output. Cached tokens are billed at
+    /// [`ModelPricing::cached_input_per_1k`] when set and at the input rate
+    /// otherwise.
+    pub fn cost_estimate(&self, pricing: &ModelPricing) -> f64 {
+        let prompt = self.prompt_token_count.unwrap_or_default()
+            + self.tool_use_prompt_token_count.unwrap_or_default();
+        let cached = self
+            .cached_content_token_count
+            .unwrap_or_default()
+            .min(prompt);
+        let output = self.candidates_token_count.unwrap_or_default()
+            + self.thoughts_token_count.unwrap_or_default();
+
+        let cached_rate = pricing.cached_input_per_1k.unwrap_or(pricing.input_per_1k);
+        (f64::from(prompt - cached) * pricing.input_per_1k
+            + f64::from(cached) * cached_rate
+            + f64::from(output) * pricing.output_per_1k)
+            / 1000.0
+    }
+}
+
+/// Formats as `prompt/candidates/total` token counts, e.g. `12/34/46`, with
+/// unreported counts shown as 0. Thinking tokens are appended when
+/// reported, e.g. `12/34/146 (thoughts: 100)`.
+impl std::fmt::Display for UsageMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.prompt_token_count.unwrap_or_default(),
+            self.candidates_token_count.unwrap_or_default(),
+            self.total_token_count.unwrap_or_default()
+        )?;
+        match self.thoughts_token_count {
+            Some(thoughts) => write!(f, " (thoughts: {thoughts})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Per-1k-token rates for [`UsageMetadata::cost_estimate`], in any currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+    /// The rate for cached input tokens, if discounted.
+    pub cached_input_per_1k: Option<f64>,
+}
+
+impl ModelPricing {
+    pub fn new(input_per_1k: f64, output_per_1k: f64) -> Self {
+        Self {
+            input_per_1k,
+            output_per_1k,
+            cached_input_per_1k: None,
+        }
+    }
+
+    pub fn with_cached_input(mut self, cached_input_per_1k: f64) -> Self {
+        self.cached_input_per_1k = Some(cached_input_per_1k);
+        self
+    }
+}
 
 /// Request traffic type. Indicates whether the request consumes Pay-As-You-Go or
 /// Provisioned Throughput quota.
@@ -519,9 +1584,8 @@ pub enum TrafficType {
     Other,
 }
 
-
 /// Content Part modality
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Modality {
     /// Unspecified modality.
@@ -543,24 +1607,93 @@ pub enum Modality {
     Other,
 }
 
+impl Modality {
+    /// The wire name, e.g. `IMAGE`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Modality::ModalityUnspecified => "MODALITY_UNSPECIFIED",
+            Modality::Text => "TEXT",
+            Modality::Image => "IMAGE",
+            Modality::Video => "VIDEO",
+            Modality::Audio => "AUDIO",
+            Modality::Document => "DOCUMENT",
+            Modality::Thoughts => "THOUGHTS",
+            Modality::Other => "OTHER",
+        }
+    }
+}
 
 /// Config for thinking features.
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ThinkingConfig {
-
     /// Indicates whether to include thoughts in the response. If true, thoughts
     /// are returned only when available.
     pub include_thoughts: bool,
     /// The number of thoughts tokens that the model should generate.
-    pub thinking_budget: Option<u32>,
+    ///
+    /// A budget of 0 disables thinking on models that allow it, and
+    /// [`ThinkingConfig::DYNAMIC_BUDGET`] lets the model decide. Valid ranges
+    /// depend on the model, e.g. 0 to 24576 for Gemini 2.5 Flash, and 128 to
+    /// 32768 for Gemini 2.5 Pro, which cannot disable thinking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<i32>,
     /// Controls the maximum depth of the model's internal reasoning process
     /// before it produces a response. If not specified, the default is HIGH.
     /// Recommended for Gemini 3 or later models. Use with earlier models
     /// results in an error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub thinking_level: Option<ThinkingLevel>,
 }
 
+impl ThinkingConfig {
+    /// The budget that lets the model adjust thinking to the complexity of
+    /// the request.
+    pub const DYNAMIC_BUDGET: i32 = -1;
+
+    /// Lets the model decide how much to think.
+    pub fn dynamic() -> Self {
+        Self {
+            thinking_budget: Some(Self::DYNAMIC_BUDGET),
+            ..Default::default()
+        }
+    }
+
+    /// Turns thinking off (a budget of 0).
+    ///
+    /// `include_thoughts` must stay `false`, as there are no thoughts to
+    /// include.
+    pub fn disabled() -> Self {
+        Self::budget(0)
+    }
+
+    /// Limits thinking to `tokens` thought tokens.
+    pub fn budget(tokens: u32) -> Self {
+        Self {
+            thinking_budget: Some(i32::try_from(tokens).unwrap_or(i32::MAX)),
+            ..Default::default()
+        }
+    }
+
+    /// Rejects `include_thoughts` with thinking disabled, which the API
+    /// refuses with a less obvious error, and negative budgets other than
+    /// [`ThinkingConfig::DYNAMIC_BUDGET`].
+    pub fn validate(&self) -> Result<(), crate::GeminiError> {
+        if let Some(budget @ ..=-2) = self.thinking_budget {
+            return Err(crate::GeminiError::InvalidRequest(format!(
+                "`thinking_budget` must be -1 (dynamic) or at least 0, got {budget}"
+            )));
+        }
+        if self.include_thoughts && self.thinking_budget == Some(0) {
+            return Err(crate::GeminiError::InvalidRequest(
+                "`include_thoughts` cannot be enabled when thinking is disabled with a budget of 0"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Allow user to specify how much to think using enum instead of integer
 /// budget.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
@@ -585,7 +1718,6 @@ pub enum ThinkingLevel {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Candidate {
-
     /// Generated content returned from the model.
     ///
     /// This field is not always populated, e.g.:
@@ -602,7 +1734,6 @@ pub struct Candidate {
     /// one rating per category.
     pub safety_ratings: Option<Vec<SafetyRating>>,
 
-
     /// Citation information for model-generated candidate.
     ///
     /// This field may be populated with recitation information for any text
@@ -632,6 +1763,140 @@ pub struct Candidate {
     pub index: Option<u32>,
 }
 
+impl Candidate {
+    fn parts(&self) -> impl Iterator<Item = &Part> {
+        self.content.iter().flat_map(|content| content.parts.iter())
+    }
+
+    fn merge(&mut self, other: Candidate) {
+        if let Some(other_content) = other.content {
+            match &mut self.content {
+                Some(content) => {
+                    for part in other_content.parts {
+                        match (content.parts.last_mut(), part) {
+                            (Some(Part::Text { text }), Part::Text { text: next }) => {
+                                text.push_str(&next)
+                            }
+                            (_, part) => content.parts.push(part),
+                        }
+                    }
+                }
+                None => self.content = Some(other_content),
+            }
+        }
+
+        if other.finish_reason.is_some() {
+            self.finish_reason = other.finish_reason;
+        }
+        if other.safety_ratings.is_some() {
+            self.safety_ratings = other.safety_ratings;
+        }
+        if other.citation_metadata.is_some() {
+            self.citation_metadata = other.citation_metadata;
+        }
+        if other.token_count.is_some() {
+            self.token_count = other.token_count;
+        }
+        self.grounding_attributions
+            .extend(other.grounding_attributions);
+        if other.grounding_metadata.is_some() {
+            self.grounding_metadata = other.grounding_metadata;
+        }
+        if other.avg_logprobs.is_some() {
+            self.avg_logprobs = other.avg_logprobs;
+        }
+        if other.url_context_metadata.is_some() {
+            self.url_context_metadata = other.url_context_metadata;
+        }
+    }
+
+    /// The generated content, if any.
+    pub fn content(&self) -> Option<&Content> {
+        self.content.as_ref()
+    }
+
+    /// Code generated by the model when the code execution tool is enabled.
+    pub fn executable_code(&self) -> Vec<&ExecutableCode> {
+        self.parts()
+            .filter_map(|part| match part {
+                Part::ExecutableCode { code } => Some(code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Results of running the code returned by [`Candidate::executable_code`].
+    pub fn code_execution_results(&self) -> Vec<&CodeExecutionResult> {
+        self.parts()
+            .filter_map(|part| match part {
+                Part::CodeExecutionResult { result } => Some(result),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Inline and file data parts whose MIME type starts with `prefix`,
+    /// e.g. `"audio/"` or `"image/png"`.
+    pub fn parts_with_mime_prefix(&self, prefix: &str) -> Vec<&Part> {
+        self.parts()
+            .filter(|part| match part {
+                Part::InlineData { data, .. } => data.mime_type.starts_with(prefix),
+                Part::FileData { data, .. } => data.mime_type.starts_with(prefix),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// The concatenated text parts, excluding thoughts.
+    fn text(&self) -> String {
+        self.parts()
+            .filter_map(|part| match part {
+                Part::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The text of each grounding attribution, in order. See
+    /// [`GroundingAttribution::source_id`] for where each one came from.
+    pub fn attribution_texts(&self) -> Vec<String> {
+        self.grounding_attributions
+            .iter()
+            .map(|attribution| {
+                attribution
+                    .content
+                    .parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        Part::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The sources cited by this candidate.
+    pub fn citations(&self) -> Vec<&CitationSource> {
+        self.citation_metadata
+            .iter()
+            .flat_map(|metadata| metadata.citation_sources.iter())
+            .collect()
+    }
+
+    /// Each citation alongside the cited span of the candidate's text.
+    ///
+    /// Citations whose byte range does not fall on character boundaries of
+    /// the text are skipped.
+    pub fn cited_spans(&self) -> Vec<(&CitationSource, String)> {
+        let text = self.text();
+        self.citations()
+            .into_iter()
+            .filter_map(|citation| Some((citation, citation.span(&text)?.to_string())))
+            .collect()
+    }
+}
+
 /// Attribution for a source that contributed to an answer.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
@@ -643,18 +1908,34 @@ pub struct GroundingAttribution {
     pub content: Content,
 }
 
-
 /// Identifier for the source contributing to this attribution.
+///
+/// Sent as a `oneof`, e.g. `{"groundingPassage": {"passageId": "a"}}`.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-#[serde(untagged, rename_all = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub enum AttributionSourceId {
-
     /// Identifier for an inline passage.
     GroundingPassage(GroundingPassageId),
     /// Identifier for a Chunk fetched via Semantic Retriever.
     SemanticRetrieverChunk(SemanticRetrieverChunk),
 }
 
+/// Formats a label for the source: the passage ID, with the part index if
+/// set, e.g. `intro#2`, or the chunk name.
+impl std::fmt::Display for AttributionSourceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributionSourceId::GroundingPassage(passage) => {
+                f.write_str(passage.passage_id.as_deref().unwrap_or("passage"))?;
+                match passage.part_index {
+                    Some(index) => write!(f, "#{index}"),
+                    None => Ok(()),
+                }
+            }
+            AttributionSourceId::SemanticRetrieverChunk(chunk) => f.write_str(&chunk.chunk),
+        }
+    }
+}
 
 /// Identifier for a part within a `GroundingPassage`.
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
@@ -684,7 +1965,6 @@ pub struct SemanticRetrieverChunk {
     pub chunk: String,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct CitationMetadata {
@@ -710,6 +1990,18 @@ pub struct CitationSource {
     pub license: Option<String>,
 }
 
+impl CitationSource {
+    /// The cited segment of `text`, the concatenated text of the candidate.
+    ///
+    /// Returns `None` without an `end_index`, or if the byte range is out of
+    /// bounds or splits a character.
+    pub fn span<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let start = self.start_index.unwrap_or_default() as usize;
+        let end = self.end_index? as usize;
+        text.get(start..end)
+    }
+}
+
 /// Safety rating for a piece of content.
 ///
 /// The safety rating contains the category of harm and the harm probability
@@ -719,12 +2011,12 @@ pub struct CitationSource {
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SafetyRating {
-
     /// The category for this rating.
     pub category: HarmCategory,
     /// The probability of harm for this content.
     pub probability: HarmProbability,
     /// Was this content blocked because of this rating?
+    #[serde(default)]
     pub blocked: bool,
 }
 
@@ -746,7 +2038,6 @@ pub enum HarmProbability {
     Other,
 }
 
-
 // HarmCategory
 //
 // The category of a rating.
@@ -754,26 +2045,78 @@ pub enum HarmProbability {
 // These categories cover various kinds of harms that developers may wish to
 // adjust.
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum HarmCategory {
     /// Default value. This value is unused.
     #[default]
+    #[serde(rename = "HARM_CATEGORY_UNSPECIFIED")]
     HarmCategoryUnspecified,
+    #[serde(rename = "HARM_CATEGORY_DEROGATORY")]
     Derogatory,
+    #[serde(rename = "HARM_CATEGORY_TOXICITY")]
     Toxicity,
+    #[serde(rename = "HARM_CATEGORY_VIOLENCE")]
     Violence,
+    #[serde(rename = "HARM_CATEGORY_SEXUAL")]
     Sexual,
+    #[serde(rename = "HARM_CATEGORY_MEDICAL")]
     Medical,
+    #[serde(rename = "HARM_CATEGORY_DANGEROUS")]
     Dangerous,
+    #[serde(rename = "HARM_CATEGORY_HARASSMENT")]
     Harassment,
+    #[serde(rename = "HARM_CATEGORY_HATE_SPEECH")]
     HateSpeech,
+    #[serde(rename = "HARM_CATEGORY_SEXUALLY_EXPLICIT")]
     SexuallyExplicit,
+    #[serde(rename = "HARM_CATEGORY_DANGEROUS_CONTENT")]
     DangerousContent,
+    #[serde(rename = "HARM_CATEGORY_CIVIC_INTEGRITY")]
     CivicIntegrity,
     #[serde(other)]
     Other,
 }
 
+/// Safety setting, affecting the safety-blocking behavior for a category.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SafetySetting {
+    /// The category for this setting.
+    pub category: HarmCategory,
+    /// Controls the probability threshold at which harm is blocked.
+    pub threshold: HarmBlockThreshold,
+}
+
+impl SafetySetting {
+    pub fn new(category: HarmCategory, threshold: HarmBlockThreshold) -> Self {
+        Self {
+            category,
+            threshold,
+        }
+    }
+}
+
+/// Block at and beyond a specified harm probability.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmBlockThreshold {
+    /// Threshold is unspecified.
+    #[default]
+    HarmBlockThresholdUnspecified,
+    /// Content with NEGLIGIBLE will be allowed.
+    BlockLowAndAbove,
+    /// Content with NEGLIGIBLE and LOW will be allowed.
+    BlockMediumAndAbove,
+    /// Content with NEGLIGIBLE, LOW, and MEDIUM will be allowed.
+    BlockOnlyHigh,
+    /// All content will be allowed.
+    BlockNone,
+    /// Turn off the safety filter. Unlike `BlockNone`, safety ratings are
+    /// not computed, and some categories such as civic integrity only
+    /// accept this value to disable blocking.
+    Off,
+    #[serde(other)]
+    Other,
+}
 
 /// Metadata returned to client when grounding is enabled.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -941,14 +2284,11 @@ pub enum FinishReason {
     UnexpectedToolCall,
 }
 
-
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged, rename_all = "camelCase")]
 pub enum Part {
     /// Standard text part.
-    Text { 
-        text: String 
-    },
+    Text { text: String },
     /// Thought / Reasoning part (Gemini 3).
     Thought {
         text: String,
@@ -958,37 +2298,45 @@ pub enum Part {
     /// Inline binary data.
     InlineData {
         #[serde(rename = "inlineData")]
-        data: InlineData
+        data: InlineData,
+        /// Part fields this crate does not model, such as `videoMetadata`,
+        /// kept when deserializing and sent alongside the data.
+        #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+        extra: serde_json::Map<String, Value>,
     },
     /// Data stored in a file (e.g. via File API).
     FileData {
         #[serde(rename = "fileData")]
-        data: FileData
+        data: FileData,
+        /// Part fields this crate does not model, such as `videoMetadata`,
+        /// kept when deserializing and sent alongside the data.
+        #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+        extra: serde_json::Map<String, Value>,
     },
     /// A call to a tool/function.
     FunctionCall {
         #[serde(rename = "functionCall")]
-        call: FunctionCall
+        call: FunctionCall,
     },
     /// A response from a tool/function.
     FunctionResponse {
         #[serde(rename = "functionResponse")]
-        response: FunctionResponse
+        response: FunctionResponse,
     },
     /// Executable code (e.g. Python for code execution).
     ExecutableCode {
         #[serde(rename = "executableCode")]
-        code: ExecutableCode
+        code: ExecutableCode,
     },
     /// Result of code execution.
     CodeExecutionResult {
         #[serde(rename = "codeExecutionResult")]
-        result: Value
+        result: CodeExecutionResult,
     },
     /// Opaque thought signature for stateful reasoning (Gemini 3).
     ThoughtSignature {
         #[serde(rename = "thoughtSignature")]
-        signature: String
+        signature: String,
     },
 }
 
@@ -1003,13 +2351,14 @@ impl Part {
             thought: true,
         }
     }
-    
+
     pub fn inline_data(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
         Self::InlineData {
             data: InlineData {
                 mime_type: mime_type.into(),
                 data: data.into(),
-            }
+            },
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -1018,20 +2367,51 @@ impl Part {
             data: FileData {
                 mime_type: mime_type.into(),
                 file_uri: file_uri.into(),
-            }
+            },
+            extra: serde_json::Map::new(),
         }
     }
-}
 
+    /// References a file uploaded with
+    /// [`crate::FilesClient::upload_file`] by its URI and MIME type.
+    pub fn from_file(file: &File) -> Self {
+        Self::file_data(&file.mime_type, &file.uri)
+    }
+
+    /// Image MIME types accepted by [`Part::from_image_bytes_autodetect`].
+    pub const SUPPORTED_IMAGE_MIME_TYPES: [&'static str; 5] = [
+        "image/png",
+        "image/jpeg",
+        "image/webp",
+        "image/gif",
+        "image/heif",
+    ];
+
+    /// Creates an inline image part, detecting the MIME type from the
+    /// image's magic bytes rather than a file name.
+    ///
+    /// Returns [`crate::GeminiError::InvalidRequest`] if the bytes are not a
+    /// PNG, JPEG, WEBP, GIF or HEIC/HEIF image.
+    pub fn from_image_bytes_autodetect(bytes: &[u8]) -> Result<Self, crate::GeminiError> {
+        let mime_type = infer::get(bytes)
+            .map(|kind| kind.mime_type())
+            .filter(|mime_type| Self::SUPPORTED_IMAGE_MIME_TYPES.contains(mime_type))
+            .ok_or_else(|| {
+                crate::GeminiError::InvalidRequest(format!(
+                    "unsupported image type, expected one of {}",
+                    Self::SUPPORTED_IMAGE_MIME_TYPES.join(", ")
+                ))
+            })?;
+        Ok(Self::inline_data(mime_type, crate::base64_encode(bytes)))
+    }
+}
 
 /// [DEPRECATED] Use [Part] instead.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 #[allow(deprecated)]
 #[deprecated(since = "0.10.0", note = "Use Part instead")]
 pub struct ContentPart {
-
-
     #[serde(default, skip_serializing_if = "is_false")]
     pub thought: bool,
     #[allow(deprecated)]
@@ -1042,6 +2422,11 @@ pub struct ContentPart {
     pub metadata: Option<serde_json::Value>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub thought_signature: Option<String>,
+    /// Fields this crate does not model, kept when deserializing and
+    /// written back alongside the part's data when serializing, e.g. into a
+    /// [`crate::GeminiClient::generate_content_raw`] body.
+    #[serde(flatten, default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 #[allow(deprecated)]
@@ -1049,12 +2434,35 @@ impl From<Part> for ContentPart {
     fn from(part: Part) -> Self {
         match part {
             Part::Text { text } => ContentPart::new_text(&text, false),
-            Part::InlineData { data } => ContentPart::new_inline_data(&data.mime_type, &data.data, false),
-            Part::FileData { data } => ContentPart::new_file_data(&data.mime_type, &data.file_uri),
-            Part::FunctionCall { call } => ContentPart::new_function_call(call.id.as_deref(), &call.name, call.arguments, false),
-            Part::FunctionResponse { response } => ContentPart::new_function_response(response.id.as_deref(), &response.name, response.response.content),
-            Part::ExecutableCode { code } => ContentPart::new_executable_code(&code.code),
-            Part::CodeExecutionResult { result } => ContentPart::new_code_execution_result(result),
+            Part::InlineData { data, extra } => ContentPart {
+                extra,
+                ..ContentPart::from(ContentData::InlineData(data))
+            },
+            Part::FileData { data, extra } => ContentPart {
+                extra,
+                ..ContentPart::from(ContentData::FileData(data))
+            },
+            Part::FunctionCall { call } => ContentPart::new_function_call(
+                call.id.as_deref(),
+                &call.name,
+                call.arguments,
+                false,
+            ),
+            Part::FunctionResponse { response } => ContentPart::new_function_response(
+                response.id.as_deref(),
+                &response.name,
+                response.response.content,
+            ),
+            Part::ExecutableCode { code } => ContentPart {
+                data: ContentData::ExecutableCode(code),
+                thought: false,
+                metadata: None,
+                thought_signature: None,
+                extra: serde_json::Map::new(),
+            },
+            Part::CodeExecutionResult { result } => ContentPart::new_code_execution_result(
+                serde_json::to_value(result).unwrap_or_default(),
+            ),
             Part::Thought { text, .. } => ContentPart::new_text(&text, true),
             Part::ThoughtSignature { signature } => {
                 let mut cp = ContentPart::new_text("", false);
@@ -1065,52 +2473,130 @@ impl From<Part> for ContentPart {
     }
 }
 
+/// Fails with [`crate::GeminiError::Json`] if a code execution result does
+/// not match [`CodeExecutionResult`], and with
+/// [`crate::GeminiError::InvalidRequest`] if the part has `metadata`, or
+/// `extra` fields on anything but inline or file data, which a [`Part`]
+/// cannot carry.
 #[allow(deprecated)]
-impl From<ContentPart> for Part {
-
-
-    fn from(cp: ContentPart) -> Self {
+impl TryFrom<ContentPart> for Part {
+    type Error = crate::GeminiError;
+
+    fn try_from(cp: ContentPart) -> Result<Self, Self::Error> {
+        if cp.metadata.is_some() {
+            return Err(crate::GeminiError::InvalidRequest(
+                "ContentPart::metadata cannot be converted into a Part".to_string(),
+            ));
+        }
+        let carries_extra = matches!(
+            cp.data,
+            ContentData::InlineData(_) | ContentData::FileData(_)
+        );
+        if !cp.extra.is_empty() && !carries_extra {
+            return Err(crate::GeminiError::InvalidRequest(
+                "only inline and file data parts can carry ContentPart::extra fields".to_string(),
+            ));
+        }
         if cp.thought {
             if let ContentData::Text(t) = cp.data {
-                return Part::Thought { text: t, thought: true };
+                return Ok(Part::Thought {
+                    text: t,
+                    thought: true,
+                });
             }
         }
         if let Some(sig) = cp.thought_signature {
-            return Part::ThoughtSignature { signature: sig };
+            return Ok(Part::ThoughtSignature { signature: sig });
         }
-        match cp.data {
+        Ok(match cp.data {
             ContentData::Text(t) => Part::Text { text: t },
-            ContentData::InlineData(d) => Part::InlineData { data: d },
-            ContentData::FileData(d) => Part::FileData { data: d },
+            ContentData::InlineData(d) => Part::InlineData {
+                data: d,
+                extra: cp.extra,
+            },
+            ContentData::FileData(d) => Part::FileData {
+                data: d,
+                extra: cp.extra,
+            },
             ContentData::FunctionCall(c) => Part::FunctionCall { call: c },
             ContentData::FunctionResponse(r) => Part::FunctionResponse { response: r },
             ContentData::ExecutableCode(c) => Part::ExecutableCode { code: c },
-            ContentData::CodeExecutionResult(v) => Part::CodeExecutionResult { result: v },
-        }
+            ContentData::CodeExecutionResult(v) => Part::CodeExecutionResult {
+                result: CodeExecutionResult::deserialize(&v).map_err(|error| {
+                    crate::GeminiError::Json {
+                        data: v.to_string(),
+                        error,
+                    }
+                })?,
+            },
+        })
     }
 }
 
-
 #[allow(deprecated)]
-impl ContentPart {
-    pub fn new_text(text: &str, thought: bool) -> Self {
-        Self {
-            data: ContentData::Text(text.to_string()),
-            thought,
+impl From<ContentData> for ContentPart {
+    fn from(data: ContentData) -> Self {
+        ContentPart {
+            data,
+            thought: false,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
+}
 
-    pub fn new_inline_data(mime_type: &str, data: &str, thought: bool) -> Self {
-        Self {
-            data: ContentData::InlineData(InlineData {
+#[allow(deprecated)]
+impl From<&str> for ContentPart {
+    fn from(text: &str) -> Self {
+        ContentPart::new_text(text, false)
+    }
+}
+
+#[allow(deprecated)]
+impl From<String> for ContentPart {
+    fn from(text: String) -> Self {
+        ContentData::Text(text).into()
+    }
+}
+
+/// Builds a [`Role::User`] turn from legacy parts. See
+/// [`Part::try_from`] for when this fails.
+#[allow(deprecated)]
+impl TryFrom<Vec<ContentPart>> for Content {
+    type Error = crate::GeminiError;
+
+    fn try_from(parts: Vec<ContentPart>) -> Result<Self, Self::Error> {
+        let parts = parts
+            .into_iter()
+            .map(Part::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Content::multimodal(Role::User, parts))
+    }
+}
+
+#[allow(deprecated)]
+impl ContentPart {
+    pub fn new_text(text: &str, thought: bool) -> Self {
+        Self {
+            data: ContentData::Text(text.to_string()),
+            thought,
+            metadata: None,
+            thought_signature: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    pub fn new_inline_data(mime_type: &str, data: &str, thought: bool) -> Self {
+        Self {
+            data: ContentData::InlineData(InlineData {
                 mime_type: mime_type.to_string(),
                 data: data.to_string(),
             }),
             thought,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -1123,6 +2609,7 @@ impl ContentPart {
             thought: false,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -1141,17 +2628,20 @@ impl ContentPart {
             thought,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 
     pub fn new_executable_code(code: &str) -> Self {
         Self {
             data: ContentData::ExecutableCode(ExecutableCode {
+                language: None,
                 code: code.to_string(),
             }),
             thought: false,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -1161,6 +2651,7 @@ impl ContentPart {
             thought: false,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -1174,6 +2665,7 @@ impl ContentPart {
             thought: false,
             metadata: None,
             thought_signature: None,
+            extra: serde_json::Map::new(),
         }
     }
 }
@@ -1182,14 +2674,30 @@ fn is_false(value: &bool) -> bool {
     !*value
 }
 
+/// Rebuilds every object in `value` with its keys in sorted order.
+fn sort_object_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_object_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_object_keys).collect()),
+        other => other,
+    }
+}
+
 /// [DEPRECATED] Use [Part] instead.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[allow(deprecated)]
 #[deprecated(since = "0.10.0", note = "Use Part instead")]
 pub enum ContentData {
-
-
     Text(String),
     InlineData(InlineData),
     FileData(FileData),
@@ -1199,21 +2707,59 @@ pub enum ContentData {
     CodeExecutionResult(Value),
 }
 
+/// Defaults to empty text, so that [`ContentPart`] can derive `Default`.
+#[allow(deprecated)]
+impl Default for ContentData {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FunctionCall {
-    #[serde(default)]
+    /// Identifies the call among parallel calls in the same turn. Echo it
+    /// in the matching [`FunctionResponse::id`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     pub name: String,
     #[serde(default, rename = "args")]
     pub arguments: serde_json::Value,
 }
 
+/// The result of a function handler. Any error type works with `?`,
+/// including `anyhow::Error` and `thiserror` enums.
+pub type FunctionResult = Result<Value, Box<dyn std::error::Error + Send + Sync>>;
+
+impl FunctionCall {
+    /// Runs `handler` with the call's arguments, converting a handler error
+    /// into [`crate::GeminiError::FunctionExecution`].
+    pub fn call_with<F>(&self, handler: F) -> Result<Value, crate::GeminiError>
+    where
+        F: FnOnce(&Value) -> FunctionResult,
+    {
+        handler(&self.arguments).map_err(|error| crate::GeminiError::FunctionExecution {
+            name: self.name.clone(),
+            message: error.to_string(),
+        })
+    }
+
+    /// Builds the response to this call, carrying over its `id` and `name`
+    /// so parallel calls are correlated correctly.
+    pub fn respond(&self, content: Value) -> FunctionResponse {
+        FunctionResponse {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            response: FunctionResponsePayload { content },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FunctionResponse {
-    #[serde(default)]
+    /// The [`FunctionCall::id`] this response answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     pub name: String,
     pub response: FunctionResponsePayload,
@@ -1225,12 +2771,59 @@ pub struct FunctionResponsePayload {
     pub content: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecutableCode {
+    /// Programming language of the `code`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
     pub code: String,
 }
 
+/// Supported programming languages for the generated code.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Language {
+    /// Unspecified language. This value should not be used.
+    #[default]
+    LanguageUnspecified,
+    /// Python >= 3.10, with numpy and simpy available.
+    Python,
+    #[serde(other)]
+    Other,
+}
+
+/// Result of executing an [`ExecutableCode`] part.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeExecutionResult {
+    /// Outcome of the code execution.
+    pub outcome: Outcome,
+    /// Contains stdout when code execution is successful, stderr or other
+    /// description otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// Enumeration of possible outcomes of the code execution.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Outcome {
+    /// Unspecified status. This value should not be used.
+    #[default]
+    OutcomeUnspecified,
+    /// Code execution completed successfully.
+    OutcomeOk,
+    /// Code execution finished but with a failure. `output` should contain
+    /// the stderr.
+    OutcomeFailed,
+    /// Code execution ran for too long, and was cancelled. There may or may
+    /// not be a partial output present.
+    OutcomeDeadlineExceeded,
+    #[serde(other)]
+    Other,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct InlineData {
@@ -1238,6 +2831,39 @@ pub struct InlineData {
     pub data: String,
 }
 
+impl InlineData {
+    /// The approximate size limit for a single inline part, after decoding.
+    pub const MAX_BYTES: usize = 7 * 1024 * 1024;
+
+    /// The size of the data once decoded from base64, in bytes.
+    pub fn decoded_len(&self) -> usize {
+        let data = self.data.trim_end_matches('=');
+        data.len() * 3 / 4
+    }
+
+    /// Checks that `data` is valid base64, which the API otherwise rejects
+    /// with an unhelpful 400. Both the standard and URL-safe alphabets are
+    /// accepted, with or without padding.
+    pub fn validate(&self) -> Result<(), crate::GeminiError> {
+        use base64::engine::{general_purpose, DecodePaddingMode, GeneralPurpose};
+        use base64::{alphabet, Engine as _};
+
+        let config = general_purpose::GeneralPurposeConfig::new()
+            .with_decode_padding_mode(DecodePaddingMode::Indifferent);
+        let standard = GeneralPurpose::new(&alphabet::STANDARD, config);
+        let url_safe = GeneralPurpose::new(&alphabet::URL_SAFE, config);
+
+        match standard.decode(&self.data) {
+            Ok(_) => Ok(()),
+            Err(_) if url_safe.decode(&self.data).is_ok() => Ok(()),
+            Err(error) => Err(crate::GeminiError::InvalidRequest(format!(
+                "inline data ({}) is not valid base64: {error}",
+                self.mime_type
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FileData {
@@ -1245,6 +2871,24 @@ pub struct FileData {
     pub file_uri: String,
 }
 
+impl FileData {
+    /// Checks that `file_uri` is a remote reference, such as a `files/...`
+    /// name or an `https://` or `gs://` URI, rather than e.g. a local path.
+    pub fn validate(&self) -> Result<(), crate::GeminiError> {
+        const SCHEMES: [&str; 3] = ["files/", "https://", "gs://"];
+        if SCHEMES
+            .iter()
+            .any(|scheme| self.file_uri.starts_with(scheme))
+        {
+            return Ok(());
+        }
+        Err(crate::GeminiError::InvalidRequest(format!(
+            "file URI `{}` must start with `files/`, `https://` or `gs://`; upload local files first",
+            self.file_uri
+        )))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Model {
@@ -1267,6 +2911,164 @@ pub struct Model {
     pub top_k: Option<f32>,
 }
 
+impl Model {
+    /// Whether the model lists `method` (e.g. `"countTokens"`) in its
+    /// supported generation methods.
+    pub fn supports(&self, method: &str) -> bool {
+        self.supported_generation_methods
+            .iter()
+            .any(|supported| supported == method)
+    }
+
+    /// Whether the model can be used with `generateContent`.
+    pub fn supports_generate_content(&self) -> bool {
+        self.supports("generateContent")
+    }
+
+    /// Whether the model can be used with `embedContent`.
+    pub fn supports_embed_content(&self) -> bool {
+        self.supports("embedContent")
+    }
+}
+
+/// Commonly used Gemini models.
+///
+/// Methods taking a model name still accept any `&str`, so models not listed
+/// here can be used directly; this enum only guards against typos in the
+/// common ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GeminiModel {
+    Gemini20Flash,
+    Gemini20FlashLite,
+    Gemini25Pro,
+    Gemini25Flash,
+    Gemini25FlashLite,
+    Gemini3ProPreview,
+    Gemini3FlashPreview,
+    GeminiEmbedding001,
+    TextEmbedding004,
+}
+
+impl GeminiModel {
+    /// Every listed model. New models are added in minor releases.
+    pub const ALL: &'static [GeminiModel] = &[
+        GeminiModel::Gemini20Flash,
+        GeminiModel::Gemini20FlashLite,
+        GeminiModel::Gemini25Pro,
+        GeminiModel::Gemini25Flash,
+        GeminiModel::Gemini25FlashLite,
+        GeminiModel::Gemini3ProPreview,
+        GeminiModel::Gemini3FlashPreview,
+        GeminiModel::GeminiEmbedding001,
+        GeminiModel::TextEmbedding004,
+    ];
+
+    /// The model name as used in request URLs, e.g. `gemini-2.5-flash`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GeminiModel::Gemini20Flash => "gemini-2.0-flash",
+            GeminiModel::Gemini20FlashLite => "gemini-2.0-flash-lite",
+            GeminiModel::Gemini25Pro => "gemini-2.5-pro",
+            GeminiModel::Gemini25Flash => "gemini-2.5-flash",
+            GeminiModel::Gemini25FlashLite => "gemini-2.5-flash-lite",
+            GeminiModel::Gemini3ProPreview => "gemini-3-pro-preview",
+            GeminiModel::Gemini3FlashPreview => "gemini-3-flash-preview",
+            GeminiModel::GeminiEmbedding001 => "gemini-embedding-001",
+            GeminiModel::TextEmbedding004 => "text-embedding-004",
+        }
+    }
+}
+
+impl AsRef<str> for GeminiModel {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Display for GeminiModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for GeminiModel {
+    type Err = crate::GeminiError;
+
+    /// Parses a model name, with or without the `models/` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.strip_prefix("models/").unwrap_or(s);
+        GeminiModel::ALL
+            .iter()
+            .copied()
+            .find(|model| model.as_str() == name)
+            .ok_or_else(|| crate::GeminiError::UnknownModel(s.to_string()))
+    }
+}
+
+/// A single page of results from the `models.list` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListModelsResponse {
+    #[serde(default)]
+    pub models: Vec<Model>,
+    pub next_page_token: Option<String>,
+}
+
+/// Content that has been preprocessed and can be used in subsequent requests
+/// to the model.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedContent {
+    /// Output only. The resource name, e.g. `cachedContents/abc123`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// The model the cache is created for, e.g. `models/gemini-2.5-flash`.
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contents: Vec<Content>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_config: Option<ToolConfig>,
+    /// Input only. Time to live, as a duration string such as `"300s"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+    /// Timestamp after which the resource is considered expired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expire_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_metadata: Option<CachedContentUsageMetadata>,
+}
+
+impl CachedContent {
+    /// Sets the time to live of the cache.
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl = Some(Self::format_ttl(ttl));
+        self
+    }
+
+    pub(crate) fn format_ttl(ttl: std::time::Duration) -> String {
+        format!("{}s", ttl.as_secs_f64())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedContentUsageMetadata {
+    /// Total number of tokens that the cached content consumes.
+    #[serde(default)]
+    pub total_token_count: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct File {
@@ -1282,53 +3084,531 @@ pub struct File {
     pub state: FileState,
 }
 
+impl File {
+    /// Whether the file has expired, or will within `margin` from now.
+    ///
+    /// Files with an unparseable `expiration_time` are treated as not
+    /// expiring.
+    pub fn expires_within(&self, margin: std::time::Duration) -> bool {
+        let Some(expires_at) = parse_rfc3339_seconds(&self.expiration_time) else {
+            return false;
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        expires_at <= now.saturating_add(margin.as_secs() as i64)
+    }
+}
+
+/// Parses an RFC 3339 timestamp such as `2024-05-20T12:34:56.789Z` into
+/// seconds since the Unix epoch, truncating fractional seconds.
+fn parse_rfc3339_seconds(timestamp: &str) -> Option<i64> {
+    let (date, time) = timestamp.split_once(['T', 't'])?;
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let (time, offset_seconds) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let split = time.rfind(['+', '-'])?;
+        let (time, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        (time, sign * (hours * 3600 + minutes * 60))
+    };
+    let mut time = time.splitn(3, ':');
+    let hours: i64 = time.next()?.parse().ok()?;
+    let minutes: i64 = time.next()?.parse().ok()?;
+    let seconds = time.next()?;
+    let seconds: i64 = match seconds.split_once('.') {
+        Some((seconds, fraction)) => {
+            if fraction.is_empty() || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            seconds.parse().ok()?
+        }
+        None => seconds.parse().ok()?,
+    };
+    // Seconds may be 60 on a leap second, which is folded into the next one.
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hours > 23
+        || minutes > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    // Days from civil, see https://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds - offset_seconds)
+}
+
+/// A single page of results from the `files.list` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFilesResponse {
+    #[serde(default)]
+    pub files: Vec<File>,
+    pub next_page_token: Option<String>,
+}
+
+/// A Semantic Retriever corpus, a collection of documents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Corpus {
+    /// Output only. The resource name, e.g. `corpora/my-corpus-123`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+/// A document within a corpus, a collection of chunks.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    /// Output only. The resource name, e.g. `corpora/123/documents/abc`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// User provided metadata, usable in `MetadataFilter`s.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_metadata: Vec<CustomMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+/// A passage of text within a document, the unit of semantic retrieval.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Chunk {
+    /// Output only. The resource name, e.g.
+    /// `corpora/123/documents/abc/chunks/xyz`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub data: ChunkData,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_metadata: Vec<CustomMetadata>,
+    /// Output only. Whether the chunk has been embedded and is searchable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<ChunkState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+impl Chunk {
+    /// Creates a chunk holding `text`.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            data: ChunkData {
+                string_value: text.into(),
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkData {
+    pub string_value: String,
+}
+
+/// The processing state of a `Chunk`.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-pub enum FileState {
+pub enum ChunkState {
     #[default]
     StateUnspecified,
-    Processing,
-    Active,
-    Failed,
+    /// The chunk is being embedded.
+    StatePendingProcessing,
+    /// The chunk is searchable.
+    StateActive,
+    /// The chunk failed processing.
+    StateFailed,
+    #[serde(other)]
+    Other,
 }
 
+/// A key-value pair attached to a document or chunk.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomMetadata {
+    pub key: String,
+    #[serde(flatten)]
+    pub value: CustomMetadataValue,
+}
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CustomMetadataValue {
+    StringValue(String),
+    StringListValue { values: Vec<String> },
+    NumericValue(f64),
+}
 
-    use serde_json::json;
+/// Request body for `corpora.query`, a semantic search over a corpus.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCorpusRequest {
+    pub query: String,
+    /// Filters on `Document` and `Chunk` custom metadata. Filters on
+    /// different keys are ANDed together.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata_filters: Vec<MetadataFilter>,
+    /// The maximum number of chunks to return, at most 100. Defaults to 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results_count: Option<i32>,
+}
 
-    use super::{
-        FunctionDeclaration, SchemaType,
-    };
+impl QueryCorpusRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+}
 
+/// A filter on the custom metadata key `key`. Conditions are ORed together.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataFilter {
+    pub key: String,
+    pub conditions: Vec<Condition>,
+}
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub operation: ConditionOperator,
+    #[serde(flatten)]
+    pub value: ConditionValue,
+}
 
-    #[test]
-    fn function_declaration_serialization() {
-        use super::Schema;
-        let declaration = FunctionDeclaration {
-            name: "lookup_status".to_string(),
-            description: "Looks up service status".to_string(),
-            parameters: Some(Schema {
-                schema_type: SchemaType::Object,
-                properties: Some(HashMap::from([(
-                    "service".to_string(),
-                    Schema {
-                        schema_type: SchemaType::String,
-                        description: Some("Service name".to_string()),
-                        ..Default::default()
-                    },
-                )])),
-                required: Some(vec!["service".to_string()]),
-                ..Default::default()
-            }),
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConditionValue {
+    StringValue(String),
+    NumericValue(f64),
+}
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConditionOperator {
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+    NotEqual,
+    /// For string list values, the list contains the given value.
+    Includes,
+    /// For string list values, the list does not contain the given value.
+    Excludes,
+}
 
-            response: None,
-        };
+/// Response from `corpora.query`, ordered by decreasing relevance.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryCorpusResponse {
+    #[serde(default)]
+    pub relevant_chunks: Vec<RelevantChunk>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RelevantChunk {
+    #[serde(default)]
+    pub chunk_relevance_score: f64,
+    pub chunk: Chunk,
+}
+
+/// A single page of results from the `corpora.list` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCorporaResponse {
+    #[serde(default)]
+    pub corpora: Vec<Corpus>,
+    pub next_page_token: Option<String>,
+}
+
+/// A single page of results from the `corpora.documents.list` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDocumentsResponse {
+    #[serde(default)]
+    pub documents: Vec<Document>,
+    pub next_page_token: Option<String>,
+}
+
+/// A single page of results from the `corpora.documents.chunks.list`
+/// endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListChunksResponse {
+    #[serde(default)]
+    pub chunks: Vec<Chunk>,
+    pub next_page_token: Option<String>,
+}
+
+/// A fine-tuned model created with the model tuning API.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TunedModel {
+    /// Output only. The resource name, e.g. `tunedModels/abc`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The base model to tune, e.g. `models/gemini-1.5-flash-001-tuning`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<i32>,
+    /// Output only. The state of the tuned model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<TunedModelState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub create_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+    pub tuning_task: TuningTask,
+}
+
+/// The state of a tuned model.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TunedModelState {
+    /// The default value. This value is unused.
+    #[default]
+    StateUnspecified,
+    /// The model is being created.
+    Creating,
+    /// The model is ready to be used.
+    Active,
+    /// The model failed to be created.
+    Failed,
+    #[serde(other)]
+    Other,
+}
+
+/// Tuning tasks that create tuned models.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningTask {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete_time: Option<String>,
+    /// Input only. The data used for tuning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_data: Option<TuningDataset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperparameters: Option<Hyperparameters>,
+}
+
+/// Hyperparameters controlling the tuning process.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Hyperparameters {
+    /// The number of training epochs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch_count: Option<i32>,
+    /// The batch size.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<i32>,
+    /// The learning rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub learning_rate: Option<f64>,
+}
+
+/// Dataset for training or validation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningDataset {
+    pub examples: TuningExamples,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningExamples {
+    pub examples: Vec<TuningExample>,
+}
+
+/// A single example for tuning.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TuningExample {
+    /// Text model input.
+    pub text_input: String,
+    /// The expected model output.
+    pub output: String,
+}
+
+/// A single page of results from the `tunedModels.list` endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ListTunedModelsResponse {
+    #[serde(default)]
+    pub tuned_models: Vec<TunedModel>,
+    pub next_page_token: Option<String>,
+}
+
+/// A long-running operation, such as a tuning job.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    /// The operation name, e.g. `tunedModels/abc/operations/xyz`.
+    pub name: String,
+    /// Service-specific progress metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+    /// Whether the operation has completed, either with `response` or
+    /// `error` set.
+    #[serde(default)]
+    pub done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+}
+
+/// A quota exceeded by a rate limited request, from the `QuotaFailure`
+/// details of a 429 response.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaViolation {
+    /// The exceeded metric, e.g.
+    /// `generativelanguage.googleapis.com/generate_content_free_tier_requests`.
+    #[serde(default)]
+    pub quota_metric: String,
+    /// The exceeded limit, e.g.
+    /// `GenerateRequestsPerDayPerProjectPerModel-FreeTier`.
+    #[serde(default)]
+    pub quota_id: String,
+    /// The dimensions the limit applies to, e.g. `model` and `location`.
+    #[serde(default)]
+    pub quota_dimensions: HashMap<String, String>,
+    /// The limit's value, e.g. `"200"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_value: Option<String>,
+}
+
+impl QuotaViolation {
+    /// Whether the limit resets daily, in which case retrying within
+    /// minutes will not help.
+    pub fn is_per_day(&self) -> bool {
+        self.quota_id.contains("PerDay")
+    }
+
+    /// Whether the limit resets every minute.
+    pub fn is_per_minute(&self) -> bool {
+        self.quota_id.contains("PerMinute")
+    }
+}
+
+/// Response from the media upload endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadFileResponse {
+    pub file: File,
+}
+
+/// How to poll a file while waiting for it to become `ACTIVE`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilePollOptions {
+    /// Time to wait between state checks.
+    pub poll_interval: std::time::Duration,
+    /// Maximum time to wait before giving up.
+    pub timeout: std::time::Duration,
+}
+
+impl Default for FilePollOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_secs(2),
+            timeout: std::time::Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileState {
+    #[default]
+    StateUnspecified,
+    Processing,
+    Active,
+    Failed,
+}
 
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    #[allow(deprecated)]
+    use super::{
+        BlockReason, Candidate, ChunkState, Condition, ConditionOperator, ConditionValue, Content,
+        ContentBuilder, ContentEmbedding, ContentPart, CustomMetadataValue, EmbedContentRequest,
+        FileData, FunctionCall, FunctionDeclaration, FunctionResponse, FunctionResponsePayload,
+        GeminiModel, GeminiSchema, GenerateContentRequest, GenerateContentResponse,
+        GenerationConfig, HarmCategory, HarmProbability, IndexMap, InlineData, LatLng,
+        MetadataFilter, ModelPricing, OpenAiMessage, Outcome, Part, PromptFeedback,
+        QueryCorpusRequest, QueryCorpusResponse, RetrievalConfig, Role, Schema, SchemaType,
+        TaskType, ThinkingConfig, ToolConfig, UsageMetadata,
+    };
+
+    #[test]
+    fn function_declaration_serialization() {
+        use super::Schema;
+        let declaration = FunctionDeclaration {
+            name: "lookup_status".to_string(),
+            description: "Looks up service status".to_string(),
+            parameters: Some(Schema {
+                schema_type: SchemaType::Object,
+                properties: Some(IndexMap::from([(
+                    "service".to_string(),
+                    Schema {
+                        schema_type: SchemaType::String,
+                        description: Some("Service name".to_string()),
+                        ..Default::default()
+                    },
+                )])),
+                required: Some(vec!["service".to_string()]),
+                ..Default::default()
+            }),
+
+            response: None,
+        };
 
         let serialized = serde_json::to_value(&declaration).expect("declaration should serialize");
         let object = serialized
@@ -1345,4 +3625,1556 @@ mod tests {
             Some(&json!(["service"]))
         );
     }
+
+    #[test]
+    fn blocked_prompt_converts_to_error() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [
+                    { "category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH" }
+                ]
+            },
+            "usageMetadata": {}
+        }))
+        .expect("response should deserialize");
+
+        let feedback = response.blocked().expect("prompt should be blocked");
+        assert_eq!(feedback.block_reason, Some(BlockReason::Safety));
+        assert_eq!(
+            feedback.safety_ratings[0].category,
+            HarmCategory::Harassment
+        );
+        assert_eq!(
+            feedback.safety_ratings[0].probability,
+            HarmProbability::High
+        );
+        assert!(matches!(
+            response.into_result(),
+            Err(crate::GeminiError::Blocked(PromptFeedback {
+                block_reason: Some(BlockReason::Safety),
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn content_builder_appends_parts_in_order() {
+        let path = std::env::temp_dir().join(format!(
+            "gemini-client-content-builder-{}.png",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"png").expect("write test image");
+
+        let content = ContentBuilder::new(Role::User)
+            .text("Describe these")
+            .inline("text/plain", "hi")
+            .image_path(&path)
+            .expect("image should be read")
+            .file_uri("application/pdf", "files/abc")
+            .build();
+        std::fs::remove_file(&path).expect("remove test image");
+
+        assert_eq!(content.role, Some(Role::User));
+        assert_eq!(
+            content.parts,
+            vec![
+                Part::text("Describe these"),
+                Part::inline_data("text/plain", "aGk="),
+                Part::inline_data("image/png", "cG5n"),
+                Part::file_data("application/pdf", "files/abc"),
+            ]
+        );
+
+        assert!(matches!(
+            ContentBuilder::new(Role::User).image_path(path),
+            Err(crate::GeminiError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn safety_ratings_without_block_reason_are_not_a_block() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "promptFeedback": {
+                "safetyRatings": [
+                    { "category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE" }
+                ]
+            }
+        }))
+        .expect("response should deserialize");
+
+        assert!(response.blocked().is_none());
+        assert!(response.into_result().is_ok());
+    }
+
+    #[test]
+    fn system_prompt_serializes_without_role() {
+        let request = GenerateContentRequest::default().with_system_prompt("Be brief.");
+
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize"),
+            json!({ "systemInstruction": { "parts": [{ "text": "Be brief." }] } })
+        );
+    }
+
+    #[test]
+    fn force_function_sets_any_mode() {
+        let request = GenerateContentRequest::default().force_function("get_weather");
+
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize"),
+            json!({
+                "toolConfig": {
+                    "functionCallingConfig": {
+                        "mode": "ANY",
+                        "allowedFunctionNames": ["get_weather"]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn candidate_code_execution_accessors() {
+        let candidate: Candidate = serde_json::from_value(json!({
+            "content": {
+                "role": "model",
+                "parts": [
+                    { "executableCode": { "language": "PYTHON", "code": "print(1 + 1)" } },
+                    { "codeExecutionResult": { "outcome": "OUTCOME_OK", "output": "2\n" } }
+                ]
+            }
+        }))
+        .expect("candidate should deserialize");
+
+        assert_eq!(candidate.executable_code()[0].code, "print(1 + 1)");
+        let results = candidate.code_execution_results();
+        assert_eq!(results[0].outcome, Outcome::OutcomeOk);
+        assert_eq!(results[0].output.as_deref(), Some("2\n"));
+    }
+
+    #[test]
+    fn rank_by_similarity_orders_descending() {
+        let embedding = |values: &[f32]| ContentEmbedding {
+            values: values.to_vec(),
+        };
+        let query = embedding(&[1.0, 0.0]);
+        let docs = [
+            embedding(&[0.0, 1.0]),
+            embedding(&[1.0, 0.0]),
+            embedding(&[1.0, 1.0]),
+        ];
+
+        let ranked =
+            ContentEmbedding::rank_by_similarity(&query, &docs).expect("dimensions should match");
+        assert_eq!(
+            ranked.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![1, 2, 0]
+        );
+
+        assert!(matches!(
+            query.dot_product(&embedding(&[1.0])),
+            Err(crate::GeminiError::DimensionMismatch {
+                expected: 2,
+                actual: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn embed_request_title_requires_retrieval_document() {
+        let mut request = EmbedContentRequest {
+            model: "models/text-embedding-004".to_string(),
+            task_type: Some(TaskType::RetrievalDocument),
+            title: Some("Handbook".to_string()),
+            ..Default::default()
+        };
+
+        let serialized = serde_json::to_value(&request).expect("request should serialize");
+        assert_eq!(serialized["taskType"], json!("RETRIEVAL_DOCUMENT"));
+        assert_eq!(serialized["title"], json!("Handbook"));
+        assert!(request.validate().is_ok());
+
+        request.task_type = Some(TaskType::RetrievalQuery);
+        assert!(matches!(
+            request.validate(),
+            Err(crate::GeminiError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn gemini_model_round_trips_through_str() {
+        for &model in GeminiModel::ALL {
+            assert_eq!(model.to_string().parse::<GeminiModel>().ok(), Some(model));
+        }
+        assert_eq!(
+            "models/gemini-2.5-flash".parse::<GeminiModel>().ok(),
+            Some(GeminiModel::Gemini25Flash)
+        );
+        assert!(matches!(
+            "gemini-2.5-flahs".parse::<GeminiModel>(),
+            Err(crate::GeminiError::UnknownModel(_))
+        ));
+    }
+
+    #[test]
+    fn merge_concatenates_streamed_text() {
+        let chunk = |value: serde_json::Value| -> GenerateContentResponse {
+            serde_json::from_value(value).expect("chunk should deserialize")
+        };
+        let mut response = chunk(json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hello, " }] }, "index": 0 }],
+            "usageMetadata": { "promptTokenCount": 3 }
+        }));
+        assert!(!response.is_complete());
+
+        response.merge(chunk(json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "world!" }] },
+                "finishReason": "STOP",
+                "index": 0
+            }],
+            "usageMetadata": { "promptTokenCount": 3, "totalTokenCount": 7 }
+        })));
+
+        assert!(response.is_complete());
+        assert_eq!(
+            response.candidates[0]
+                .content
+                .as_ref()
+                .map(|content| &content.parts),
+            Some(&vec![super::Part::text("Hello, world!")])
+        );
+        assert_eq!(
+            response
+                .usage_metadata
+                .and_then(|usage| usage.total_token_count),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn generation_config_validates_bounds() {
+        assert!(GenerationConfig::default().candidates(0).is_err());
+        assert!(GenerationConfig::default()
+            .stop_sequences(vec!["END".to_string(); 6])
+            .is_err());
+
+        let config = GenerationConfig::default()
+            .candidates(2)
+            .and_then(|config| config.stop_sequences(vec!["END".to_string()]))
+            .expect("config should be valid");
+        assert_eq!(config.candidate_count, Some(2));
+        assert_eq!(config.stop_sequences, vec!["END".to_string()]);
+
+        assert!(GenerationConfig::default().with_logprobs(6).is_err());
+        let config = GenerationConfig::default()
+            .with_logprobs(3)
+            .expect("logprobs should be valid");
+        assert_eq!(config.response_logprobs, Some(true));
+        assert_eq!(config.logprobs, Some(3));
+    }
+
+    #[test]
+    fn function_call_handler_errors_convert() {
+        let call = FunctionCall {
+            id: None,
+            name: "divide".to_string(),
+            arguments: json!({ "numerator": "one" }),
+        };
+
+        let result = call.call_with(|arguments| {
+            let numerator: f64 = arguments["numerator"]
+                .as_str()
+                .unwrap_or_default()
+                .parse()?;
+            Ok(json!(numerator / 2.0))
+        });
+
+        assert!(matches!(
+            result,
+            Err(crate::GeminiError::FunctionExecution { ref name, .. }) if name == "divide"
+        ));
+    }
+
+    #[test]
+    fn function_turns_use_model_and_user_roles() {
+        let call = FunctionCall {
+            id: Some("call-1".to_string()),
+            name: "get_weather".to_string(),
+            arguments: json!({ "location": "London" }),
+        };
+        let response = FunctionResponse {
+            id: call.id.clone(),
+            name: call.name.clone(),
+            response: FunctionResponsePayload {
+                content: json!({ "forecast": "rain" }),
+            },
+        };
+
+        let history = vec![
+            Content::function_calls([call]),
+            Content::function_responses([response]),
+        ];
+
+        assert_eq!(
+            serde_json::to_value(&history).expect("history should serialize"),
+            json!([
+                {
+                    "role": "model",
+                    "parts": [{ "functionCall": { "id": "call-1", "name": "get_weather", "args": { "location": "London" } } }]
+                },
+                {
+                    "role": "user",
+                    "parts": [{ "functionResponse": { "id": "call-1", "name": "get_weather", "response": { "content": { "forecast": "rain" } } } }]
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn streamed_chunk_without_usage_metadata_parses() {
+        let chunk: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "Hi" }] } }]
+        }))
+        .expect("chunk should deserialize");
+
+        assert_eq!(chunk.usage_metadata, None);
+        assert_eq!(chunk.response_id(), None);
+    }
+
+    #[test]
+    fn image_bytes_mime_type_is_detected() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0];
+        assert!(matches!(
+            Part::from_image_bytes_autodetect(&png),
+            Ok(Part::InlineData { data, .. }) if data.mime_type == "image/png"
+        ));
+
+        assert!(matches!(
+            Part::from_image_bytes_autodetect(b"%PDF-1.7"),
+            Err(crate::GeminiError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn continue_from_appends_model_and_user_turns() {
+        let truncated: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": "Once upon a ti" }] },
+                "finishReason": "MAX_TOKENS"
+            }]
+        }))
+        .expect("response should deserialize");
+
+        let request = GenerateContentRequest::default().continue_from(&truncated);
+
+        assert_eq!(request.contents.len(), 2);
+        assert_eq!(request.contents[0].role, Some(super::Role::Model));
+        assert_eq!(
+            request.contents[0].parts,
+            vec![Part::text("Once upon a ti")]
+        );
+        assert_eq!(request.contents[1].role, Some(super::Role::User));
+        assert!(matches!(
+            &request.contents[1].parts[0],
+            Part::Text { text } if text.contains("mid-word")
+        ));
+    }
+
+    #[test]
+    fn continue_from_treats_boundaries_as_complete_words() {
+        let instruction = |text: &str, finish_reason: &str| {
+            let truncated: GenerateContentResponse = serde_json::from_value(json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [{ "text": text }] },
+                    "finishReason": finish_reason
+                }]
+            }))
+            .expect("response should deserialize");
+            match GenerateContentRequest::default()
+                .continue_from(&truncated)
+                .contents
+                .pop()
+                .map(|content| content.parts)
+                .as_deref()
+            {
+                Some([Part::Text { text }]) => text.clone(),
+                other => panic!("unexpected instruction: {other:?}"),
+            }
+        };
+
+        assert!(instruction("The total is 1024", "MAX_TOKENS").contains("cut off."));
+        assert!(instruction("Once upon a time.", "MAX_TOKENS").contains("cut off."));
+        assert!(instruction("Once upon a time ", "MAX_TOKENS").contains("cut off."));
+        assert!(instruction("Once upon a ti", "STOP").contains("cut off."));
+        assert!(instruction("Once upon a ti", "MAX_TOKENS").contains("mid-word"));
+    }
+
+    #[test]
+    fn disabled_thinking_rejects_included_thoughts() {
+        let mut request = GenerateContentRequest {
+            generation_config: Some(GenerationConfig {
+                thinking_config: Some(ThinkingConfig::disabled()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(request.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize")["generationConfig"]
+                ["thinkingConfig"],
+            json!({ "includeThoughts": false, "thinkingBudget": 0 })
+        );
+
+        if let Some(config) = request.generation_config.as_mut() {
+            config.thinking_config = Some(ThinkingConfig {
+                include_thoughts: true,
+                ..ThinkingConfig::disabled()
+            });
+        }
+        assert!(matches!(
+            request.validate(),
+            Err(crate::GeminiError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn seed_serializes() {
+        let config = GenerationConfig {
+            seed: Some(42),
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::to_value(&config).expect("config should serialize"),
+            json!({ "seed": 42, "temperature": 0.0 })
+        );
+    }
+
+    #[test]
+    fn rfc3339_timestamps_parse_to_unix_seconds() {
+        assert_eq!(
+            super::parse_rfc3339_seconds("1970-01-01T00:00:00Z"),
+            Some(0)
+        );
+        assert_eq!(
+            super::parse_rfc3339_seconds("2024-05-20T12:34:56.789012Z"),
+            Some(1_716_208_496)
+        );
+        assert_eq!(
+            super::parse_rfc3339_seconds("2024-05-20T14:34:56+02:00"),
+            Some(1_716_208_496)
+        );
+        assert_eq!(super::parse_rfc3339_seconds("not a timestamp"), None);
+    }
+
+    #[test]
+    fn rfc3339_timestamps_handle_offsets_and_fractions() {
+        let parse = super::parse_rfc3339_seconds;
+        let expected = Some(1_716_208_496);
+
+        assert_eq!(parse("2024-05-20T07:04:56-05:30"), expected);
+        assert_eq!(parse("2024-05-20T07:04:56.999-05:30"), expected);
+        assert_eq!(parse("2024-05-20T12:34:56.5+00:00"), expected);
+        assert_eq!(parse("2024-05-21T02:34:56+14:00"), expected);
+        assert_eq!(parse("2024-05-20t12:34:56z"), expected);
+        // Offsets can move the instant across a day or year boundary.
+        assert_eq!(
+            parse("2024-05-19T23:30:00-01:00"),
+            parse("2024-05-20T00:30:00Z")
+        );
+        assert_eq!(
+            parse("2025-01-01T00:30:00+01:00"),
+            parse("2024-12-31T23:30:00Z")
+        );
+        assert_eq!(parse("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse("1969-12-31T23:59:59Z"), Some(-1));
+
+        for invalid in [
+            "2024-05-20T12:34:56",
+            "2024-05-20T12:34:56+0200",
+            "2024-05-20T12:34:56.Z",
+            "2024-05-20T12:34:56.12aZ",
+            "2024-13-20T12:34:56Z",
+            "2024-05-00T12:34:56Z",
+            "2024-05-20T24:00:00Z",
+            "2024-05-20T12:60:00Z",
+            "2024-05-20T12:34:56+24:00",
+            "2024-05-20",
+        ] {
+            assert_eq!(parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn estimated_bytes_includes_base64_inline_data() {
+        let raw = vec![0u8; 3 * 1024];
+        let request = GenerateContentRequest {
+            contents: vec![Content::multimodal(
+                super::Role::User,
+                vec![Part::inline_data("image/png", crate::base64_encode(&raw))],
+            )],
+            ..Default::default()
+        };
+
+        let estimated = request.estimated_bytes();
+        assert_eq!(
+            estimated,
+            serde_json::to_vec(&request)
+                .expect("request should serialize")
+                .len()
+        );
+        assert!(estimated >= raw.len() * 4 / 3);
+        assert!(!request.exceeds_inline_limit());
+    }
+
+    #[test]
+    fn corpus_query_metadata_serialization() {
+        let request = QueryCorpusRequest {
+            metadata_filters: vec![MetadataFilter {
+                key: "chunk.custom_metadata.genre".to_string(),
+                conditions: vec![Condition {
+                    operation: ConditionOperator::Equal,
+                    value: ConditionValue::StringValue("fiction".to_string()),
+                }],
+            }],
+            results_count: Some(5),
+            ..QueryCorpusRequest::new("Who wrote it?")
+        };
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize"),
+            json!({
+                "query": "Who wrote it?",
+                "metadataFilters": [{
+                    "key": "chunk.custom_metadata.genre",
+                    "conditions": [{ "operation": "EQUAL", "stringValue": "fiction" }]
+                }],
+                "resultsCount": 5
+            })
+        );
+
+        let response: QueryCorpusResponse = serde_json::from_value(json!({
+            "relevantChunks": [{
+                "chunkRelevanceScore": 0.82,
+                "chunk": {
+                    "name": "corpora/c/documents/d/chunks/x",
+                    "data": { "stringValue": "Once upon a time" },
+                    "customMetadata": [
+                        { "key": "tags", "stringListValue": { "values": ["a", "b"] } },
+                        { "key": "year", "numericValue": 1999 }
+                    ],
+                    "state": "STATE_ACTIVE"
+                }
+            }]
+        }))
+        .expect("response should deserialize");
+        let chunk = &response.relevant_chunks[0].chunk;
+        assert_eq!(chunk.data.string_value, "Once upon a time");
+        assert_eq!(chunk.state, Some(ChunkState::StateActive));
+        assert_eq!(
+            chunk.custom_metadata[1].value,
+            CustomMetadataValue::NumericValue(1999.0)
+        );
+    }
+
+    #[test]
+    fn candidate_summaries_bundle_per_candidate_scores() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [
+                {
+                    "content": { "role": "model", "parts": [{ "text": "Hel" }, { "text": "lo" }] },
+                    "finishReason": "STOP",
+                    "avgLogprobs": -0.25,
+                    "tokenCount": 2
+                },
+                {
+                    "content": { "role": "model", "parts": [{ "text": "Hi" }] },
+                    "finishReason": "MAX_TOKENS",
+                    "avgLogprobs": -0.75,
+                    "index": 3
+                }
+            ]
+        }))
+        .expect("response should deserialize");
+
+        let summaries = response.candidate_summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].index, 0);
+        assert_eq!(summaries[0].text, "Hello");
+        assert_eq!(summaries[0].finish_reason, Some(super::FinishReason::Stop));
+        assert_eq!(summaries[0].avg_logprobs, Some(-0.25));
+        assert_eq!(summaries[0].token_count, Some(2));
+        assert_eq!(summaries[1].index, 3);
+        assert_eq!(summaries[1].token_count, None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn content_parts_from_strings() {
+        use super::{ContentData, ContentPart, Role};
+
+        let part: ContentPart = "hello".into();
+        assert!(!part.thought);
+        assert_eq!(part.data, ContentData::Text("hello".to_string()));
+        assert_eq!(ContentPart::from(String::from("hello")), part);
+
+        let content = Content::try_from(vec![part, "world".into()]).expect("text converts");
+        assert_eq!(content.role, Some(Role::User));
+        assert_eq!(
+            content.parts,
+            vec![Part::text("hello"), Part::text("world")]
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn malformed_code_execution_results_fail_to_convert() {
+        use super::ContentPart;
+
+        let part = ContentPart::new_code_execution_result(json!({ "outcome": 42 }));
+        assert!(matches!(
+            Part::try_from(part),
+            Err(crate::GeminiError::Json { .. })
+        ));
+
+        let part = ContentPart::new_code_execution_result(
+            json!({ "outcome": "OUTCOME_OK", "output": "4\n" }),
+        );
+        assert!(matches!(
+            Part::try_from(part),
+            Ok(Part::CodeExecutionResult { result }) if result.output.as_deref() == Some("4\n")
+        ));
+    }
+
+    #[test]
+    fn enum_response_round_trip() {
+        let config = GenerationConfig::default()
+            .enum_response(vec!["positive".to_string(), "negative".to_string()]);
+        let value = serde_json::to_value(&config).expect("config should serialize");
+        assert_eq!(value["responseMimeType"], "text/x.enum");
+        assert_eq!(
+            value["responseSchema"],
+            json!({ "type": "STRING", "enum": ["positive", "negative"] })
+        );
+
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{ "content": { "role": "model", "parts": [{ "text": "positive\n" }] } }]
+        }))
+        .expect("response should deserialize");
+        assert_eq!(response.enum_value().as_deref(), Some("positive"));
+        assert_eq!(GenerateContentResponse::default().enum_value(), None);
+    }
+
+    #[test]
+    fn role_wire_strings() {
+        use super::Role;
+
+        for (role, wire) in [(Role::User, "user"), (Role::Model, "model")] {
+            assert_eq!(
+                serde_json::to_value(role).expect("role should serialize"),
+                json!(wire)
+            );
+            assert_eq!(
+                serde_json::from_value::<Role>(json!(wire)).expect("role should deserialize"),
+                role
+            );
+        }
+
+        // The API only accepts `user` and `model`; function responses are sent
+        // as `user` and system instructions carry no role.
+        for wire in ["tool", "system", "Model", "USER"] {
+            assert!(
+                serde_json::from_value::<Role>(json!(wire)).is_err(),
+                "{wire}"
+            );
+        }
+    }
+
+    #[test]
+    fn parallel_function_responses_echo_call_ids() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {
+                    "role": "model",
+                    "parts": [
+                        { "functionCall": { "id": "a", "name": "get_weather", "args": { "location": "Paris" } } },
+                        { "functionCall": { "id": "b", "name": "get_weather", "args": { "location": "Rome" } } },
+                        { "functionCall": { "name": "get_time", "args": {} } }
+                    ]
+                }
+            }]
+        }))
+        .expect("response should deserialize");
+
+        let responses: Vec<FunctionResponse> = response.candidates[0]
+            .content
+            .as_ref()
+            .expect("candidate should have content")
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::FunctionCall { call } => Some(call.respond(call.arguments.clone())),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            serde_json::to_value(Content::function_responses(responses))
+                .expect("responses should serialize")["parts"],
+            json!([
+                { "functionResponse": { "id": "a", "name": "get_weather", "response": { "content": { "location": "Paris" } } } },
+                { "functionResponse": { "id": "b", "name": "get_weather", "response": { "content": { "location": "Rome" } } } },
+                { "functionResponse": { "name": "get_time", "response": { "content": {} } } }
+            ])
+        );
+    }
+
+    #[test]
+    fn cited_spans_use_byte_offsets() {
+        let candidate: Candidate = serde_json::from_value(json!({
+            "content": { "role": "model", "parts": [{ "text": "Café au lait " }, { "text": "is French." }] },
+            "citationMetadata": { "citationSources": [
+                { "endIndex": 5, "uri": "https://example.com/cafe" },
+                { "startIndex": 14, "endIndex": 23, "uri": "https://example.com/french" },
+                { "startIndex": 3, "endIndex": 4 }
+            ] }
+        }))
+        .expect("candidate should deserialize");
+
+        assert_eq!(candidate.citations().len(), 3);
+        let spans: Vec<(Option<&str>, String)> = candidate
+            .cited_spans()
+            .into_iter()
+            .map(|(citation, span)| (citation.uri.as_deref(), span))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (Some("https://example.com/cafe"), "Café".to_string()),
+                (Some("https://example.com/french"), "is French".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn parameter_properties_round_trip_nullable_and_format() {
+        use super::ParameterProperty;
+
+        let properties = json!({
+            "name": { "type": "string", "nullable": true },
+            "age": { "type": "integer", "nullable": true },
+            "score": { "type": "number", "nullable": false },
+            "active": { "type": "boolean", "nullable": true },
+            "tags": { "type": "array", "items": { "type": "string" }, "nullable": true },
+            "note": { "type": "string", "description": "Optional note" },
+            "timestamp": { "type": "string", "format": "date-time" },
+            "id": { "type": "integer", "format": "int64" }
+        });
+        let parsed: HashMap<String, ParameterProperty> =
+            serde_json::from_value(properties.clone()).expect("properties should deserialize");
+
+        assert!(
+            matches!(&parsed["age"], ParameterProperty::Integer(integer) if integer.nullable == Some(true))
+        );
+        assert!(
+            matches!(&parsed["note"], ParameterProperty::String(string) if string.nullable.is_none())
+        );
+        assert!(
+            matches!(&parsed["timestamp"], ParameterProperty::String(string) if string.format.as_deref() == Some("date-time"))
+        );
+        assert!(
+            matches!(&parsed["id"], ParameterProperty::Integer(integer) if integer.format.as_deref() == Some("int64"))
+        );
+        assert_eq!(
+            serde_json::to_value(&parsed).expect("properties should serialize"),
+            properties
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn numeric_parameter_bounds_round_trip() {
+        use super::ParameterProperty;
+
+        let properties = json!({
+            "priority": { "type": "integer", "minimum": 1, "maximum": 10 },
+            "ratio": { "type": "number", "minimum": 0.0, "maximum": 1.5 }
+        });
+        let parsed: HashMap<String, ParameterProperty> =
+            serde_json::from_value(properties.clone()).expect("properties should deserialize");
+
+        assert!(matches!(
+            &parsed["priority"],
+            ParameterProperty::Integer(integer) if integer.minimum == Some(1) && integer.maximum == Some(10)
+        ));
+        assert!(matches!(
+            &parsed["ratio"],
+            ParameterProperty::Number(number) if number.minimum == Some(0.0) && number.maximum == Some(1.5)
+        ));
+        assert_eq!(
+            serde_json::to_value(&parsed).expect("properties should serialize"),
+            properties
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn array_parameter_item_bounds_round_trip() {
+        use super::ParameterProperty;
+
+        let attendees = json!({
+            "type": "array",
+            "items": { "type": "array", "items": { "type": "string" }, "maxItems": 2 },
+            "minItems": 1,
+            "maxItems": 5
+        });
+        let parsed: ParameterProperty =
+            serde_json::from_value(attendees.clone()).expect("property should deserialize");
+
+        let ParameterProperty::Array(array) = &parsed else {
+            panic!("expected an array property, got {parsed:?}");
+        };
+        assert_eq!((array.min_items, array.max_items), (Some(1), Some(5)));
+        assert!(
+            matches!(&*array.items, ParameterProperty::Array(items) if items.max_items == Some(2))
+        );
+        assert_eq!(
+            serde_json::to_value(&parsed).expect("property should serialize"),
+            attendees
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn function_parameters_keep_property_order() {
+        use super::{FunctionParameters, ParameterProperty, ParameterPropertyBoolean};
+
+        let flag = || ParameterProperty::Boolean(ParameterPropertyBoolean::default());
+        let parameters = FunctionParameters {
+            parameter_type: "object".to_string(),
+            properties: IndexMap::from([("b".to_string(), flag()), ("a".to_string(), flag())]),
+            required: None,
+            property_ordering: Some(vec!["b".to_string(), "a".to_string()]),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&parameters).expect("parameters should serialize"),
+            r#"{"type":"object","properties":{"b":{"type":"boolean"},"a":{"type":"boolean"}},"propertyOrdering":["b","a"]}"#
+        );
+
+        let value = serde_json::to_value(&parameters).expect("parameters should serialize");
+        assert_eq!(
+            serde_json::from_value::<FunctionParameters>(value)
+                .expect("parameters should deserialize"),
+            parameters
+        );
+    }
+
+    #[test]
+    fn add_function_response_groups_parallel_results() {
+        let mut request = GenerateContentRequest {
+            contents: vec![Content::function_calls([
+                FunctionCall {
+                    id: Some("a".to_string()),
+                    name: "get_weather".to_string(),
+                    arguments: json!({ "location": "Paris" }),
+                },
+                FunctionCall {
+                    id: Some("b".to_string()),
+                    name: "get_weather".to_string(),
+                    arguments: json!({ "location": "Rome" }),
+                },
+            ])],
+            ..Default::default()
+        };
+
+        request.add_function_response("get_weather", Some("a"), json!({ "forecast": "sunny" }));
+        request.add_function_response("get_weather", Some("b"), json!({ "forecast": "rain" }));
+
+        assert_eq!(request.contents.len(), 2);
+        assert_eq!(
+            serde_json::to_value(&request.contents[1]).expect("content should serialize"),
+            json!({
+                "role": "user",
+                "parts": [
+                    { "functionResponse": { "id": "a", "name": "get_weather", "response": { "content": { "forecast": "sunny" } } } },
+                    { "functionResponse": { "id": "b", "name": "get_weather", "response": { "content": { "forecast": "rain" } } } }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn generation_config_sanitized_for_model_limits() {
+        let model: super::Model = serde_json::from_value(json!({
+            "name": "models/gemini-2.0-flash",
+            "version": "2.0",
+            "displayName": "Gemini 2.0 Flash",
+            "inputTokenLimit": 1048576,
+            "outputTokenLimit": 8192,
+            "supportedGenerationMethods": ["generateContent"],
+            "temperature": 1.0,
+            "maxTemperature": 2.0,
+            "topP": 0.95
+        }))
+        .expect("model should deserialize");
+
+        let config = GenerationConfig {
+            temperature: Some(2.5),
+            top_p: Some(0.9),
+            top_k: Some(40),
+            max_output_tokens: Some(65536),
+            presence_penalty: Some(0.5),
+            ..Default::default()
+        }
+        .sanitized_for(&model);
+
+        assert_eq!(config.temperature, Some(2.0));
+        assert_eq!(config.top_p, Some(0.9));
+        assert_eq!(config.top_k, None);
+        assert_eq!(config.max_output_tokens, Some(8192));
+        assert_eq!(config.presence_penalty, Some(0.5));
+    }
+
+    #[test]
+    fn candidates_without_parts_parse() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [
+                { "content": { "role": "model" }, "finishReason": "SAFETY", "index": 0 },
+                { "content": {}, "finishReason": "RECITATION", "index": 1 }
+            ]
+        }))
+        .expect("response should deserialize");
+
+        let contents: Vec<&Content> = response
+            .candidates
+            .iter()
+            .filter_map(|candidate| candidate.content.as_ref())
+            .collect();
+        assert_eq!(contents.len(), 2);
+        assert!(contents.iter().all(|content| content.parts.is_empty()));
+        assert_eq!(contents[0].role, Some(super::Role::Model));
+        assert_eq!(contents[1].role, None);
+    }
+
+    #[test]
+    fn model_content_is_ready_for_history() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello!" }] } }]
+        }))
+        .expect("response should deserialize");
+
+        let content = response
+            .model_content()
+            .expect("first candidate has content");
+        assert_eq!(content.role, Some(super::Role::Model));
+        assert_eq!(content.parts, vec![Part::text("Hello!")]);
+
+        let mut request = GenerateContentRequest::default();
+        request.contents.push(content);
+        assert_eq!(request.contents.len(), 1);
+
+        let empty = GenerateContentResponse::default();
+        assert!(empty.model_content().is_none());
+    }
+
+    #[test]
+    fn inline_data_rejects_malformed_base64() {
+        let valid = InlineData {
+            mime_type: "image/png".to_string(),
+            data: crate::base64_encode(&[0xfb, 0xff, 0x00]),
+        };
+        assert!(valid.validate().is_ok());
+
+        let url_safe_unpadded = InlineData {
+            data: "-_8".to_string(),
+            ..valid.clone()
+        };
+        assert!(url_safe_unpadded.validate().is_ok());
+
+        let malformed = InlineData {
+            data: "not base64!".to_string(),
+            ..valid.clone()
+        };
+        assert!(matches!(
+            malformed.validate(),
+            Err(crate::GeminiError::InvalidRequest(message)) if message.contains("image/png")
+        ));
+
+        let request = GenerateContentRequest {
+            contents: vec![Content {
+                parts: vec![Part::inline_data(malformed.mime_type, malformed.data)],
+                role: Some(super::Role::User),
+            }],
+            ..Default::default()
+        };
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn system_content_accepts_multimodal_parts() {
+        let request = GenerateContentRequest::default().with_system_content(Content {
+            parts: vec![
+                Part::text("Match the style of this image."),
+                Part::inline_data("image/png", crate::base64_encode(b"png")),
+                Part::file_data("image/jpeg", "https://example.com/files/abc"),
+            ],
+            role: Some(super::Role::User),
+        });
+        assert!(request.validate().is_ok());
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize")["systemInstruction"],
+            json!({
+                "parts": [
+                    { "text": "Match the style of this image." },
+                    { "inlineData": { "mimeType": "image/png", "data": "cG5n" } },
+                    {
+                        "fileData": {
+                            "mimeType": "image/jpeg",
+                            "fileUri": "https://example.com/files/abc"
+                        }
+                    }
+                ]
+            })
+        );
+
+        let request = GenerateContentRequest::default().with_system_content(Content {
+            parts: vec![Part::FunctionCall {
+                call: FunctionCall {
+                    id: None,
+                    name: "lookup".to_string(),
+                    arguments: json!({}),
+                },
+            }],
+            role: None,
+        });
+        assert!(matches!(
+            request.validate(),
+            Err(crate::GeminiError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn usage_metadata_estimates_cost_per_modality() {
+        let usage: UsageMetadata = serde_json::from_value(json!({
+            "promptTokenCount": 2000,
+            "cachedContentTokenCount": 1000,
+            "candidatesTokenCount": 600,
+            "thoughtsTokenCount": 400,
+            "totalTokenCount": 3000,
+            "candidatesTokensDetails": [
+                { "modality": "TEXT", "tokenCount": 500 },
+                { "modality": "TEXT", "tokenCount": 50 },
+                { "modality": "IMAGE", "tokenCount": 50 }
+            ]
+        }))
+        .expect("usage should deserialize");
+
+        let by_modality = usage.candidates_tokens_by_modality();
+        assert_eq!(by_modality[&super::Modality::Text], 550);
+        assert_eq!(by_modality[&super::Modality::Image], 50);
+
+        let pricing = ModelPricing::new(0.5, 2.0);
+        assert!((usage.cost_estimate(&pricing) - 3.0).abs() < 1e-9);
+        let cached = pricing.with_cached_input(0.1);
+        assert!((usage.cost_estimate(&cached) - 2.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normalize_roles_merges_adjacent_turns() {
+        let mut request = GenerateContentRequest {
+            contents: vec![
+                Content::multimodal(super::Role::User, vec![Part::text("Hi.")]),
+                Content::multimodal(super::Role::User, vec![Part::text("Are you there?")]),
+                Content::multimodal(super::Role::Model, vec![Part::text("Yes.")]),
+                Content::multimodal(super::Role::User, vec![Part::text("Great.")]),
+            ],
+            ..Default::default()
+        };
+        request.normalize_roles();
+
+        assert_eq!(
+            request.contents,
+            vec![
+                Content::multimodal(
+                    super::Role::User,
+                    vec![Part::text("Hi."), Part::text("Are you there?")]
+                ),
+                Content::multimodal(super::Role::Model, vec![Part::text("Yes.")]),
+                Content::multimodal(super::Role::User, vec![Part::text("Great.")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_block_reasons_are_kept() {
+        let reasons: Vec<BlockReason> =
+            serde_json::from_value(json!(["OTHER", "IMAGE_SAFETY", "SPII"]))
+                .expect("reasons should deserialize");
+        assert_eq!(
+            reasons,
+            vec![
+                BlockReason::Other,
+                BlockReason::ImageSafety,
+                BlockReason::Unknown("SPII".to_string()),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&reasons).expect("reasons should serialize"),
+            json!(["OTHER", "IMAGE_SAFETY", "SPII"])
+        );
+    }
+
+    #[test]
+    fn audio_timestamp_serializes_in_camel_case() {
+        let config = GenerationConfig {
+            audio_timestamp: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&config).expect("config should serialize"),
+            json!({ "audioTimestamp": true })
+        );
+    }
+
+    #[test]
+    fn openai_messages_map_to_gemini_roles() {
+        let messages: Vec<OpenAiMessage> = serde_json::from_value(json!([
+            { "role": "system", "content": "Be brief." },
+            { "role": "user", "content": "Hi" },
+            { "role": "assistant", "content": "Hello!" },
+            { "role": "user", "content": "What's 2 + 2?" },
+            { "role": "user", "content": "Show your work." }
+        ]))
+        .expect("messages should deserialize");
+
+        let request =
+            GenerateContentRequest::from_openai_messages(&messages).expect("roles are supported");
+        assert_eq!(
+            request.system_instruction,
+            Some(Content {
+                parts: vec![Part::text("Be brief.")],
+                role: None,
+            })
+        );
+        let roles: Vec<_> = request
+            .contents
+            .iter()
+            .map(|content| content.role)
+            .collect();
+        assert_eq!(
+            roles,
+            vec![
+                Some(super::Role::User),
+                Some(super::Role::Model),
+                Some(super::Role::User)
+            ]
+        );
+        assert_eq!(request.contents[2].parts.len(), 2);
+
+        let tool = OpenAiMessage {
+            role: "tool".to_string(),
+            content: "{}".to_string(),
+        };
+        assert!(GenerateContentRequest::from_openai_messages(&[tool]).is_err());
+    }
+
+    #[test]
+    fn parts_are_filtered_by_mime_prefix() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [{
+                "content": {
+                    "role": "model",
+                    "parts": [
+                        { "text": "Here you go" },
+                        { "inlineData": { "mimeType": "audio/wav", "data": "UklGRg==" } },
+                        { "fileData": { "mimeType": "video/mp4", "fileUri": "files/abc" } },
+                        { "inlineData": { "mimeType": "image/png", "data": "iVBORw==" } }
+                    ]
+                }
+            }]
+        }))
+        .expect("response should parse");
+
+        let audio = response.candidates[0].parts_with_mime_prefix("audio/");
+        assert!(matches!(
+            audio.as_slice(),
+            [Part::InlineData { data, .. }] if data.mime_type == "audio/wav"
+        ));
+        assert!(matches!(
+            response.parts_by_mime("video/").as_slice(),
+            [Part::FileData { data, .. }] if data.file_uri == "files/abc"
+        ));
+        assert_eq!(response.parts_by_mime("").len(), 3);
+        assert!(response.parts_by_mime("application/pdf").is_empty());
+    }
+
+    #[test]
+    fn schema_properties_serialize_in_declaration_order() {
+        let schema = Schema {
+            schema_type: SchemaType::Object,
+            properties: Some(
+                ["zeta", "alpha", "mu", "beta", "omega", "delta"]
+                    .into_iter()
+                    .map(|name| (name.to_string(), Schema::default()))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&schema).expect("schema should serialize");
+        let positions: Vec<usize> = ["zeta", "alpha", "mu", "beta", "omega", "delta"]
+            .iter()
+            .map(|name| json.find(&format!("\"{name}\"")).expect("property present"))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn response_schema_value_is_sent_with_sorted_keys() {
+        let config = GenerationConfig::default().with_response_schema_value(json!({
+            "type": "object",
+            "properties": { "b": { "type": "string" }, "a": { "type": "integer" } },
+            "required": ["b", "a"],
+        }));
+        assert_eq!(
+            config.response_mime_type.as_deref(),
+            Some("application/json")
+        );
+        assert!(config.response_schema.is_none());
+
+        let json = serde_json::to_string(&config).expect("config should serialize");
+        assert!(json.contains(
+            r#""_responseJsonSchema":{"properties":{"a":{"type":"integer"},"b":{"type":"string"}},"required":["b","a"],"type":"object"}"#
+        ));
+    }
+
+    #[test]
+    fn ordered_response_schema_sets_property_ordering() {
+        let schema = Schema {
+            schema_type: SchemaType::Object,
+            properties: Some(IndexMap::from([
+                ("summary".to_string(), String::schema()),
+                ("title".to_string(), String::schema()),
+            ])),
+            ..Default::default()
+        };
+        let config = GenerationConfig::default()
+            .with_ordered_response_schema(schema, vec!["title".to_string(), "summary".to_string()]);
+
+        let value = serde_json::to_value(&config).expect("config should serialize");
+        assert_eq!(value["responseMimeType"], "application/json");
+        assert_eq!(
+            value["responseSchema"]["propertyOrdering"],
+            json!(["title", "summary"])
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn content_part_round_trips_unmodeled_fields() {
+        let value = json!({
+            "fileData": {
+                "mimeType": "video/mp4",
+                "fileUri": "https://example.com/files/abc"
+            },
+            "videoMetadata": { "startOffset": "10s" }
+        });
+        let part: ContentPart = serde_json::from_value(value.clone()).expect("part should parse");
+
+        assert_eq!(part.extra["videoMetadata"], json!({ "startOffset": "10s" }));
+        assert_eq!(
+            serde_json::to_value(&part).expect("part should serialize"),
+            value
+        );
+        assert!(ContentPart::new_text("Hi", false).extra.is_empty());
+
+        let request = GenerateContentRequest {
+            contents: vec![Content {
+                parts: vec![
+                    Part::text("Summarize the clip."),
+                    Part::try_from(part.clone()).expect("file data should convert"),
+                ],
+                role: Some(super::Role::User),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize"),
+            json!({
+                "contents": [{
+                    "parts": [{ "text": "Summarize the clip." }, value],
+                    "role": "user"
+                }]
+            })
+        );
+
+        assert_eq!(
+            serde_json::from_value::<Part>(value).expect("part should parse"),
+            Part::try_from(part).expect("file data should convert")
+        );
+
+        let mut text = ContentPart::new_text("Hi", false);
+        text.extra.insert("partMetadata".to_string(), json!({}));
+        assert!(matches!(
+            Part::try_from(text),
+            Err(crate::GeminiError::InvalidRequest(_))
+        ));
+        let with_metadata = ContentPart {
+            metadata: Some(json!({ "source": "cache" })),
+            ..ContentPart::default()
+        };
+        assert!(Part::try_from(with_metadata).is_err());
+    }
+
+    #[test]
+    fn usage_displays_token_counts() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [],
+            "usageMetadata": {
+                "promptTokenCount": 12,
+                "candidatesTokenCount": 34,
+                "totalTokenCount": 46
+            }
+        }))
+        .expect("response should deserialize");
+
+        let usage = response.usage().expect("usage is reported");
+        assert_eq!(usage.to_string(), "12/34/46");
+        assert_eq!(response.total_tokens(), 46);
+        assert_eq!(response.thinking_tokens(), 0);
+        assert_eq!(GenerateContentResponse::default().total_tokens(), 0);
+
+        let thinking = UsageMetadata {
+            thoughts_token_count: Some(100),
+            total_token_count: Some(146),
+            ..usage.clone()
+        };
+        assert_eq!(thinking.to_string(), "12/34/146 (thoughts: 100)");
+    }
+
+    #[test]
+    fn file_data_requires_a_remote_uri() {
+        let file: super::File = serde_json::from_value(json!({
+            "name": "files/abc",
+            "mimeType": "application/pdf",
+            "sizeBytes": "1024",
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "expirationTime": "2025-01-03T00:00:00Z",
+            "sha256Hash": "",
+            "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc",
+            "state": "ACTIVE"
+        }))
+        .expect("file should deserialize");
+        let Part::FileData { data, .. } = Part::from_file(&file) else {
+            panic!("expected a file data part");
+        };
+        assert_eq!(data.mime_type, "application/pdf");
+        assert!(data.validate().is_ok());
+
+        let local = FileData {
+            mime_type: "application/pdf".to_string(),
+            file_uri: "./report.pdf".to_string(),
+        };
+        assert!(local.validate().is_err());
+        let request = GenerateContentRequest {
+            contents: vec![Content {
+                parts: vec![Part::file_data(local.mime_type, local.file_uri)],
+                role: None,
+            }],
+            ..Default::default()
+        };
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn typed_modalities_serialize_in_screaming_case() {
+        let config = GenerationConfig::default()
+            .with_modalities(&[super::Modality::Text, super::Modality::Image]);
+        assert_eq!(
+            serde_json::to_value(&config).expect("config should serialize"),
+            json!({ "responseModalities": ["TEXT", "IMAGE"] })
+        );
+
+        for modality in [
+            super::Modality::Thoughts,
+            super::Modality::ModalityUnspecified,
+        ] {
+            assert_eq!(
+                serde_json::to_value(modality).expect("modality should serialize"),
+                modality.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn candidate_is_found_by_index_field() {
+        let response: GenerateContentResponse = serde_json::from_value(json!({
+            "candidates": [
+                { "index": 1, "finishReason": "MAX_TOKENS" },
+                { "index": 0, "finishReason": "STOP" }
+            ]
+        }))
+        .expect("response should deserialize");
+
+        assert_eq!(response.candidates().len(), 2);
+        assert_eq!(
+            response
+                .candidate(0)
+                .and_then(|candidate| candidate.finish_reason.clone()),
+            Some(super::FinishReason::Stop)
+        );
+        assert!(response.candidate(2).is_none());
+        assert_eq!(response.into_candidates()[0].index, Some(1));
+    }
+
+    #[test]
+    fn labels_serialize_only_when_set() {
+        let request = GenerateContentRequest::default();
+        let value = serde_json::to_value(&request).expect("request should serialize");
+        assert!(value.get("labels").is_none());
+
+        let request = request.with_label("tenant", "acme");
+        assert_eq!(
+            serde_json::to_value(&request).expect("request should serialize")["labels"],
+            json!({ "tenant": "acme" })
+        );
+    }
+
+    #[test]
+    fn attributions_resolve_to_texts_and_labels() {
+        let candidate: Candidate = serde_json::from_value(json!({
+            "groundingAttributions": [
+                {
+                    "sourceId": { "groundingPassage": { "passageId": "intro", "partIndex": 2 } },
+                    "content": { "parts": [{ "text": "Rust is " }, { "text": "fast." }] }
+                },
+                {
+                    "sourceId": {
+                        "semanticRetrieverChunk": {
+                            "source": "corpora/123",
+                            "chunk": "corpora/123/documents/abc/chunks/xyz"
+                        }
+                    },
+                    "content": { "parts": [{ "text": "Rust is safe." }] }
+                }
+            ]
+        }))
+        .expect("candidate should deserialize");
+
+        assert_eq!(
+            candidate.attribution_texts(),
+            vec!["Rust is fast.".to_string(), "Rust is safe.".to_string()]
+        );
+        let labels: Vec<String> = candidate
+            .grounding_attributions
+            .iter()
+            .filter_map(|attribution| attribution.source_id.as_ref())
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            labels,
+            vec!["intro#2", "corpora/123/documents/abc/chunks/xyz"]
+        );
+    }
+
+    #[test]
+    fn oversized_inline_data_suggests_the_files_api() {
+        let small = InlineData {
+            mime_type: "image/png".to_string(),
+            data: crate::base64_encode(b"hello"),
+        };
+        assert_eq!(small.decoded_len(), 5);
+
+        let large = InlineData {
+            mime_type: "video/mp4".to_string(),
+            data: "A".repeat((InlineData::MAX_BYTES + 3) / 3 * 4),
+        };
+        let request = GenerateContentRequest {
+            contents: vec![Content {
+                parts: vec![
+                    Part::inline_data(small.mime_type, small.data),
+                    Part::inline_data(large.mime_type, large.data),
+                ],
+                role: Some(super::Role::User),
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            request.validate_inline_sizes(),
+            Err(crate::GeminiError::InvalidRequest(message)) if message.contains("upload_file")
+        ));
+        assert!(GenerateContentRequest::default()
+            .validate_inline_sizes()
+            .is_ok());
+    }
+
+    #[test]
+    fn tool_config_serializes_only_the_configs_that_are_set() {
+        let request = GenerateContentRequest::default()
+            .enable_google_maps()
+            .with_retrieval_config(RetrievalConfig {
+                lat_lng: Some(LatLng {
+                    latitude: 48.8566,
+                    longitude: 2.3522,
+                }),
+                language_code: None,
+            });
+        let value = serde_json::to_value(&request).expect("request should serialize");
+        assert_eq!(
+            value["toolConfig"],
+            json!({ "retrievalConfig": { "latLng": { "latitude": 48.8566, "longitude": 2.3522 } } })
+        );
+
+        let value = serde_json::to_value(request.auto_tools()).expect("request should serialize");
+        assert_eq!(
+            value["toolConfig"]["functionCallingConfig"],
+            json!({ "mode": "AUTO" })
+        );
+        assert!(value["toolConfig"]["retrievalConfig"].is_object());
+
+        let config: ToolConfig =
+            serde_json::from_value(json!({})).expect("an empty tool config should deserialize");
+        assert_eq!(config, ToolConfig::default());
+    }
+
+    #[test]
+    fn google_maps_tool_round_trips() {
+        let request = GenerateContentRequest::default().enable_google_maps();
+        let value = serde_json::to_value(&request).expect("request should serialize");
+        assert_eq!(value["tools"], json!([{ "googleMaps": {} }]));
+
+        let tools: Vec<super::Tool> =
+            serde_json::from_value(value["tools"].clone()).expect("tools should deserialize");
+        assert_eq!(tools, request.tools);
+    }
+
+    #[test]
+    fn google_search_tool_serializes_as_empty_object() {
+        let request = GenerateContentRequest {
+            tools: vec![super::Tool::google_search()],
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&request).expect("request should serialize");
+        assert_eq!(value["tools"], json!([{ "googleSearch": {} }]));
+    }
+
+    #[test]
+    fn dynamic_thinking_budget_serializes_as_minus_one() {
+        let config = ThinkingConfig::dynamic();
+        assert_eq!(
+            serde_json::to_value(&config).expect("config should serialize"),
+            json!({ "includeThoughts": false, "thinkingBudget": -1 })
+        );
+        assert!(config.validate().is_ok());
+
+        let invalid = ThinkingConfig {
+            thinking_budget: Some(-2),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
 }